@@ -29,7 +29,7 @@ impl Game {
     pub fn run(&mut self) {
         while !self.exit {
             self.input.update();
-            if self.input.just_pressed(Button::Start) {
+            if self.input.quit_requested() || self.input.just_pressed(Button::Start) {
                 break;
             }
 
@@ -2,7 +2,7 @@ use pixel_zero::{
     WIDTH,
     glam::ivec2,
     graphics::{Font, Frame, Graphics, color::Color},
-    input::{Button, Input},
+    input::{Button, Input, SystemHandlers},
 };
 
 use crate::screen::{Screen, main_menu::MainMenu};
@@ -22,10 +22,12 @@ impl Launcher {
             .load_font_binary(include_bytes!("../assets/cozette.psf"))
             .unwrap();
         let screen = Box::new(MainMenu::init(&font));
+        let mut input = Input::default();
+        input.set_system_handlers(SystemHandlers::all());
 
         Self {
             graphics,
-            input: Input::default(),
+            input,
             font,
             screen,
             exit: false,
@@ -35,10 +37,17 @@ impl Launcher {
     pub fn run(&mut self) {
         while !self.exit {
             self.input.update();
-            if self.input.just_pressed(Button::Start) {
+            // typing "." on a keyboard also presses Start
+            if self.input.just_pressed(Button::Start)
+                && self.input.text_input().is_empty()
+                && !self.screen.captures_buttons()
+            {
                 self.exit = true;
             }
-            let screen = self.screen.update(&self.input);
+            if self.input.quit_requested() {
+                self.exit = true;
+            }
+            let screen = self.screen.update(&mut self.input);
 
             let mut frame = Frame::default();
             frame.set_clear_color(Color::rgb(100, 150, 240));
@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+use pixel_zero::{
+    glam::ivec2,
+    graphics::{Font, Frame},
+    input::{Binding, Button, ButtonMap, ButtonProfiles, DeviceId, Input},
+};
+
+use crate::screen::{Screen, main_menu::MainMenu};
+
+/// How long to wait for a press before skipping a button.
+const SKIP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Walks through the GBA buttons, binding the next physical press to each.
+///
+/// Buttons nobody presses within [`SKIP_TIMEOUT`] are skipped and keep their previous bindings,
+/// if nothing is pressed at all, the menu is left without changing anything.
+pub struct ControlsMenu {
+    font: Font,
+    buttons: Vec<Button>,
+    index: usize,
+    // when the current button started waiting for a press
+    waiting_since: Instant,
+    skipped: Vec<Button>,
+    // the first pressed device is the one being configured
+    device: Option<DeviceId>,
+    map: ButtonMap,
+    bound: Vec<Binding>,
+}
+
+impl ControlsMenu {
+    pub fn init(font: Font) -> Self {
+        Self {
+            font,
+            buttons: Button::GBA.to_vec(),
+            index: 0,
+            waiting_since: Instant::now(),
+            skipped: Vec::new(),
+            device: None,
+            map: ButtonMap::empty(),
+            bound: Vec::new(),
+        }
+    }
+
+    fn save(&self, input: &mut Input) {
+        let Some(device) = self.device else {
+            return;
+        };
+
+        let mut profiles = input.profiles().cloned().unwrap_or_default();
+        // extended and skipped buttons keep their previous bindings
+        let mut map = self.map.clone();
+        let previous = profiles.map_for(device);
+        for button in (0..Button::BUTTON_COUNT)
+            .filter_map(Button::from_usize)
+            .filter(|button| button.is_extended() || self.skipped.contains(button))
+        {
+            for binding in previous.bindings(button) {
+                if !self.bound.contains(binding) {
+                    map.bind(button, *binding);
+                }
+            }
+        }
+        profiles.devices.insert(device, map);
+
+        match ButtonProfiles::path() {
+            Some(path) => match profiles.save(&path) {
+                Ok(()) => log::info!("saved controls for {device} to {}", path.display()),
+                Err(e) => log::error!("failed to save controls: {e}"),
+            },
+            None => log::warn!("no path to save controls to"),
+        }
+        input.set_profiles(profiles);
+    }
+}
+
+impl Screen for ControlsMenu {
+    fn update(&mut self, input: &mut Input) -> Option<Box<dyn Screen>> {
+        for press in input.raw_presses() {
+            if self.device.is_some_and(|device| device != press.device) {
+                continue;
+            }
+            // one input can only be bound to one button
+            if self.bound.contains(&press.binding) {
+                continue;
+            }
+
+            self.device = Some(press.device);
+            self.map.bind(self.buttons[self.index], press.binding);
+            self.bound.push(press.binding);
+            self.index += 1;
+            self.waiting_since = Instant::now();
+            if self.index == self.buttons.len() {
+                break;
+            }
+        }
+
+        if self.index < self.buttons.len() && self.waiting_since.elapsed() >= SKIP_TIMEOUT {
+            if self.device.is_none() {
+                return Some(Box::new(MainMenu::init(&self.font)));
+            }
+            self.skipped.push(self.buttons[self.index]);
+            self.index += 1;
+            self.waiting_since = Instant::now();
+        }
+
+        if self.index == self.buttons.len() {
+            self.save(input);
+            return Some(Box::new(MainMenu::init(&self.font)));
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let line_height = self.font.glyph_size().y.cast_signed();
+        let mut offset = 0;
+
+        let device = match self.device {
+            Some(device) => format!("Configuring {device}"),
+            None => "Configure controls".to_owned(),
+        };
+        frame.draw_text(&self.font, &device, ivec2(0, offset));
+        offset += line_height * 2;
+
+        for (index, button) in self.buttons.iter().enumerate() {
+            let text = match self.map.bindings(*button).first() {
+                Some(binding) => format!("{button}: {binding}"),
+                None if index == self.index => {
+                    let left = SKIP_TIMEOUT.saturating_sub(self.waiting_since.elapsed());
+                    let action = if self.device.is_some() {
+                        "skipping"
+                    } else {
+                        "leaving"
+                    };
+                    format!(
+                        "Press {button}... ({action} in {}s)",
+                        left.as_millis().div_ceil(1000)
+                    )
+                }
+                None if self.skipped.contains(button) => format!("{button}: skipped"),
+                None => format!("{button}:"),
+            };
+            frame.draw_text(&self.font, &text, ivec2(0, offset));
+            offset += line_height;
+        }
+    }
+
+    fn captures_buttons(&self) -> bool {
+        true
+    }
+}
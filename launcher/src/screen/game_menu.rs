@@ -54,7 +54,7 @@ impl GameMenu {
 }
 
 impl Screen for GameMenu {
-    fn update(&mut self, input: &Input) -> Option<Box<dyn Screen>> {
+    fn update(&mut self, input: &mut Input) -> Option<Box<dyn Screen>> {
         self.button_state = *input.state();
         None
     }
@@ -4,7 +4,7 @@ use pixel_zero::{
     ui::Ui,
 };
 
-use crate::screen::{Screen, game_menu::GameMenu};
+use crate::screen::{Screen, controls_menu::ControlsMenu, game_menu::GameMenu};
 
 pub struct MainMenu {
     ui: Ui,
@@ -25,7 +25,7 @@ impl MainMenu {
 }
 
 impl Screen for MainMenu {
-    fn update(&mut self, input: &Input) -> Option<Box<dyn Screen>> {
+    fn update(&mut self, input: &mut Input) -> Option<Box<dyn Screen>> {
         self.ui.update_input(input);
         self.ui.begin_frame();
 
@@ -33,6 +33,9 @@ impl Screen for MainMenu {
         if self.ui.button("Start Game") {
             return Some(Box::new(GameMenu::init(self.ui.font())));
         }
+        if self.ui.button("Configure controls") {
+            return Some(Box::new(ControlsMenu::init(self.ui.font())));
+        }
         self.ui.slider("Volume", &mut self.test_int, 0..=10, 1);
         self.ui.progress_bar(self.test_int, 0..=10);
         self.ui.label(&format!("Volume: {}", self.test_int));
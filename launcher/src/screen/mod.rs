@@ -1,9 +1,15 @@
 use pixel_zero::{graphics::Frame, input::Input};
 
+pub mod controls_menu;
 pub mod game_menu;
 pub mod main_menu;
 
 pub trait Screen {
-    fn update(&mut self, input: &Input) -> Option<Box<dyn Screen>>;
+    fn update(&mut self, input: &mut Input) -> Option<Box<dyn Screen>>;
     fn render(&self, frame: &mut Frame);
+
+    /// Whether the screen needs every button, so Start doesn't exit the launcher.
+    fn captures_buttons(&self) -> bool {
+        false
+    }
 }
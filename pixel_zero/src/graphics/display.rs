@@ -0,0 +1,261 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use drm::control::{Mode, ModeTypeFlags, connector};
+use glam::{UVec2, uvec2};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DisplayConfigError {
+    #[error("Invalid display size: {0}")]
+    InvalidSize(String),
+    #[error("Invalid refresh rate: {0}")]
+    InvalidRefreshRate(String),
+}
+
+/// A mode supported by a display connector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+    /// Whether the display reports this as its preferred mode.
+    pub preferred: bool,
+}
+
+impl DisplayMode {
+    pub(crate) fn from_drm(mode: &Mode) -> Self {
+        Self {
+            width: u32::from(mode.size().0),
+            height: u32::from(mode.size().1),
+            refresh_rate: mode.vrefresh(),
+            preferred: mode.mode_type().contains(ModeTypeFlags::PREFERRED),
+        }
+    }
+
+    #[must_use]
+    pub fn size(&self) -> UVec2 {
+        uvec2(self.width, self.height)
+    }
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}@{}", self.width, self.height, self.refresh_rate)
+    }
+}
+
+/// Clockwise rotation of the picture, for panels that are mounted rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl Rotation {
+    #[must_use]
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        Some(match degrees {
+            0 => Self::Deg0,
+            90 => Self::Deg90,
+            180 => Self::Deg180,
+            270 => Self::Deg270,
+            _ => return None,
+        })
+    }
+
+    #[must_use]
+    pub fn degrees(self) -> u32 {
+        self.quarter_turns() * 90
+    }
+
+    pub(crate) fn quarter_turns(self) -> u32 {
+        match self {
+            Self::Deg0 => 0,
+            Self::Deg90 => 1,
+            Self::Deg180 => 2,
+            Self::Deg270 => 3,
+        }
+    }
+
+    /// Whether width and height trade places.
+    pub(crate) fn is_portrait(self) -> bool {
+        matches!(self, Self::Deg90 | Self::Deg270)
+    }
+}
+
+/// A display output, like `HDMI-A-1` or `DSI-1`.
+#[derive(Debug, Clone)]
+pub struct ConnectorInfo {
+    pub name: String,
+    pub connected: bool,
+    pub modes: Vec<DisplayMode>,
+}
+
+impl ConnectorInfo {
+    pub(crate) fn from_drm(connector: &connector::Info) -> Self {
+        Self {
+            name: connector.to_string(),
+            connected: connector.state() == connector::State::Connected,
+            modes: connector
+                .modes()
+                .iter()
+                .map(DisplayMode::from_drm)
+                .collect(),
+        }
+    }
+}
+
+/// A DRM device node, like `/dev/dri/card0`.
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub path: PathBuf,
+    /// Kernel driver name, like `vc4` or `amdgpu`.
+    pub driver: String,
+    /// Whether any display is connected to this device.
+    pub connected: bool,
+}
+
+/// Selects the DRM device used for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceSelector {
+    /// Device node, like `/dev/dri/card1`.
+    Path(PathBuf),
+    /// Kernel driver name, like `vc4` or `sun4i-drm`.
+    Driver(String),
+}
+
+impl From<&str> for DeviceSelector {
+    /// Absolute paths select a device node, anything else a driver name.
+    fn from(value: &str) -> Self {
+        if value.starts_with('/') {
+            Self::Path(PathBuf::from(value))
+        } else {
+            Self::Driver(value.to_owned())
+        }
+    }
+}
+
+/// Selects the device, connector and mode `Graphics` uses.
+/// Fields that are `None` fall back to the defaults:
+/// the first device with a connected display, its first connected connector,
+/// and the smallest preferred mode with at least 50 Hz.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayConfig {
+    pub device: Option<DeviceSelector>,
+    /// Render node to allocate buffers on, like `/dev/dri/renderD128`,
+    /// for boards where the display controller and the GPU are separate devices.
+    pub render_node: Option<PathBuf>,
+    /// Connector name, like `HDMI-A-1`.
+    pub connector: Option<String>,
+    pub size: Option<UVec2>,
+    pub refresh_rate: Option<u32>,
+    pub rotation: Rotation,
+}
+
+impl DisplayConfig {
+    /// Environment variable for the connector and mode, read by [`DisplayConfig::from_env`].
+    pub const ENV_VAR: &str = "PIXEL_ZERO_DISPLAY";
+    /// Environment variable for the device, read by [`DisplayConfig::from_env`].
+    pub const DEVICE_ENV_VAR: &str = "PIXEL_ZERO_DEVICE";
+    /// Environment variable for the render node, read by [`DisplayConfig::from_env`].
+    pub const RENDER_NODE_ENV_VAR: &str = "PIXEL_ZERO_RENDER_NODE";
+    /// Environment variable for the rotation, read by [`DisplayConfig::from_env`].
+    pub const ROTATION_ENV_VAR: &str = "PIXEL_ZERO_ROTATION";
+
+    /// Reads the config from the environment.
+    ///
+    /// `PIXEL_ZERO_DISPLAY` is formatted as `connector:WIDTHxHEIGHT@HZ`, where every part is optional,
+    /// e.g. `HDMI-A-1`, `1280x720`, `@60` or `DSI-1:720x1280@60`.
+    /// Falls back to the defaults if it is unset or malformed.
+    ///
+    /// `PIXEL_ZERO_DEVICE` is a device path or driver name, see [`DeviceSelector`].
+    /// `PIXEL_ZERO_RENDER_NODE` is the path of a render node.
+    /// `PIXEL_ZERO_ROTATION` is one of `0`, `90`, `180` or `270`.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut config = match std::env::var(Self::ENV_VAR) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                log::warn!("ignoring {}={value}: {e}", Self::ENV_VAR);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+
+        config.device = std::env::var(Self::DEVICE_ENV_VAR)
+            .ok()
+            .map(|value| DeviceSelector::from(value.as_str()));
+        config.render_node = std::env::var_os(Self::RENDER_NODE_ENV_VAR).map(PathBuf::from);
+
+        if let Ok(value) = std::env::var(Self::ROTATION_ENV_VAR) {
+            match value.trim().parse().ok().and_then(Rotation::from_degrees) {
+                Some(rotation) => config.rotation = rotation,
+                None => log::warn!("ignoring {}={value}", Self::ROTATION_ENV_VAR),
+            }
+        }
+
+        config
+    }
+
+    /// Whether a mode satisfies the size and refresh rate of this config.
+    pub(crate) fn matches(&self, mode: &DisplayMode) -> bool {
+        self.size.is_none_or(|size| size == mode.size())
+            && self
+                .refresh_rate
+                .is_none_or(|refresh_rate| refresh_rate == mode.refresh_rate)
+    }
+
+    pub(crate) fn has_mode(&self) -> bool {
+        self.size.is_some() || self.refresh_rate.is_some()
+    }
+}
+
+/// Parses the connector and mode, in the format of `PIXEL_ZERO_DISPLAY`.
+impl FromStr for DisplayConfig {
+    type Err = DisplayConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (connector, mode) = match s.split_once(':') {
+            Some((connector, mode)) => (connector, mode),
+            None if s.starts_with(|c: char| c.is_ascii_digit() || c == '@') => ("", s),
+            None => (s, ""),
+        };
+
+        let (size, refresh_rate) = mode.split_once('@').unwrap_or((mode, ""));
+
+        let size = if size.is_empty() {
+            None
+        } else {
+            let (width, height) = size
+                .split_once('x')
+                .ok_or_else(|| DisplayConfigError::InvalidSize(size.to_owned()))?;
+            let width = width
+                .parse()
+                .map_err(|_| DisplayConfigError::InvalidSize(size.to_owned()))?;
+            let height = height
+                .parse()
+                .map_err(|_| DisplayConfigError::InvalidSize(size.to_owned()))?;
+            Some(uvec2(width, height))
+        };
+
+        let refresh_rate = if refresh_rate.is_empty() {
+            None
+        } else {
+            Some(
+                refresh_rate
+                    .parse()
+                    .map_err(|_| DisplayConfigError::InvalidRefreshRate(refresh_rate.to_owned()))?,
+            )
+        };
+
+        Ok(Self {
+            connector: (!connector.is_empty()).then(|| connector.to_owned()),
+            size,
+            refresh_rate,
+            ..Self::default()
+        })
+    }
+}
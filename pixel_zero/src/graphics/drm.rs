@@ -1,19 +1,37 @@
 use std::{
+    collections::HashMap,
     fs::File,
     os::{
         fd::{AsFd, BorrowedFd},
         unix::fs::FileTypeExt as _,
     },
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Once,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::ThreadId,
 };
 
 use drm::{
-    Device as DrmDevice,
-    control::{Device as ControlDevice, Mode, ModeTypeFlags, connector, crtc, framebuffer},
+    ClientCapability, Device as DrmDevice,
+    control::{
+        Device as ControlDevice, Event, Mode, ModeTypeFlags, ResourceHandles, connector, crtc,
+        framebuffer, plane, property,
+    },
 };
 use glam::UVec2;
+use nix::{
+    errno::Errno,
+    poll::{PollFd, PollFlags, PollTimeout, poll},
+};
+use parking_lot::Mutex;
 use thiserror::Error;
 
+use crate::graphics::display::{
+    ConnectorInfo, DeviceInfo, DeviceSelector, DisplayConfig, DisplayMode, Rotation,
+};
+
 #[derive(Debug, Error)]
 pub enum DrmError {
     #[error("I/O error: {0}")]
@@ -22,10 +40,13 @@ pub enum DrmError {
     NoMode,
     #[error("No Connectors found")]
     NoConnectors,
+    #[error("Connector {0} not found or not connected")]
+    ConnectorNotFound(String),
     #[error("No suitable CRTC found")]
     NoCRTC,
 }
 
+#[derive(Clone)]
 struct OriginalState {
     crtc: crtc::Info,
     framebuffer: Option<framebuffer::Handle>,
@@ -38,22 +59,48 @@ pub(crate) struct Drm {
     connector: connector::Info,
     mode: Mode,
     crtc: crtc::Info,
+    rotation: Rotation,
+    // primary plane and its rotation property, when rotating in hardware
+    rotated_plane: Option<(plane::Handle, property::Handle)>,
+    original_state: Option<OriginalState>,
+}
+
+/// What to restore when panicking, since `Drop` might never run.
+struct PanicRestore {
+    gpu: Arc<Gpu>,
     original_state: Option<OriginalState>,
+    rotated_plane: Option<(plane::Handle, property::Handle)>,
+    // the thread using the display
+    thread: ThreadId,
 }
 
+static PANIC_RESTORE: Mutex<Option<PanicRestore>> = Mutex::new(None);
+static PANIC_HOOK: Once = Once::new();
+// the display was given back by the panic hook, but the panic was caught
+static RESTORED_BY_PANIC: AtomicBool = AtomicBool::new(false);
+
+// values of the `type` and `rotation` plane properties
+const PLANE_TYPE_PRIMARY: u64 = 1;
+const ROTATE_0: u64 = 1 << 0;
+const ROTATE_180: u64 = 1 << 2;
+
 impl Drm {
-    pub(crate) fn load() -> Result<Self, DrmError> {
-        let gpu = Gpu::open()?;
+    pub(crate) fn load(config: &DisplayConfig) -> Result<Self, DrmError> {
+        let gpu = Arc::new(Gpu::open(config.device.as_ref())?);
 
         let resources = gpu.resource_handles()?;
 
-        let Some(connector) = resources
+        let mut connectors = resources
             .connectors()
             .iter()
             .flat_map(|handle| gpu.get_connector(*handle, true))
-            .find(|connector| connector.state() == connector::State::Connected)
-        else {
-            return Err(DrmError::NoConnectors);
+            .filter(|connector| connector.state() == connector::State::Connected);
+
+        let connector = match &config.connector {
+            Some(name) => connectors
+                .find(|connector| connector.to_string().eq_ignore_ascii_case(name))
+                .ok_or_else(|| DrmError::ConnectorNotFound(name.clone()))?,
+            None => connectors.next().ok_or(DrmError::NoConnectors)?,
         };
 
         let original_crtc = connector
@@ -86,41 +133,153 @@ impl Drm {
             None
         };
 
-        let mut modes: Vec<_> = connector
-            .modes()
-            .iter()
-            .filter(|mode| {
-                mode.mode_type().contains(ModeTypeFlags::PREFERRED) && mode.vrefresh() >= 50
-            })
-            .collect();
-        modes.sort_by(|a, b| {
-            (a.size().0 as u32 * a.size().1 as u32 * a.vrefresh())
-                .cmp(&(b.size().0 as u32 * b.size().1 as u32 * b.vrefresh()))
-        });
-        let mode = **modes.first().ok_or(DrmError::NoMode)?;
+        let mode = Self::select_mode(&connector, config)?;
 
-        let Some(crtc) = connector
+        let encoders: Vec<_> = connector
             .encoders()
             .iter()
             .flat_map(|handle| gpu.get_encoder(*handle))
+            .collect();
+        // an inactive connector has no crtc bound, so take one no other connector is using
+        let used_crtcs: Vec<_> = resources
+            .connectors()
+            .iter()
+            .filter(|&&handle| handle != connector.handle())
+            .filter_map(|&handle| gpu.get_connector(handle, false).ok())
+            .filter_map(|conn| conn.current_encoder())
+            .filter_map(|handle| gpu.get_encoder(handle).ok())
             .filter_map(|encoder| encoder.crtc())
+            .collect();
+        let Some(crtc) = encoders
+            .iter()
+            .filter_map(|encoder| encoder.crtc())
+            .chain(encoders.iter().flat_map(|encoder| {
+                resources
+                    .filter_crtcs(encoder.possible_crtcs())
+                    .into_iter()
+                    .filter(|crtc| !used_crtcs.contains(crtc))
+            }))
             .flat_map(|crtc| gpu.get_crtc(crtc))
             .next()
         else {
             return Err(DrmError::NoCRTC);
         };
 
-        log::info!("screen size: {}x{}", mode.size().0, mode.size().1);
+        log::info!("display: {connector} {}", DisplayMode::from_drm(&mode));
+
+        // 90 and 270 degrees would change the framebuffer size, which legacy modesetting can't do
+        // in one step, so only 180 degrees is tried in hardware, the rest is done while rendering
+        let rotated_plane = if config.rotation == Rotation::Deg180 {
+            Self::rotate_primary_plane(&gpu, &resources, crtc.handle())
+        } else {
+            None
+        };
+        log::info!(
+            "rotation: {} degrees{}",
+            config.rotation.degrees(),
+            if rotated_plane.is_some() {
+                " (hardware)"
+            } else {
+                ""
+            }
+        );
+
+        install_panic_hook();
+        *PANIC_RESTORE.lock() = Some(PanicRestore {
+            gpu: gpu.clone(),
+            original_state: original_state.clone(),
+            rotated_plane,
+            thread: std::thread::current().id(),
+        });
+        RESTORED_BY_PANIC.store(false, Ordering::Relaxed);
 
         Ok(Self {
-            gpu: Arc::new(gpu),
+            gpu,
             connector,
             mode,
             crtc,
+            rotation: config.rotation,
+            rotated_plane,
             original_state,
         })
     }
 
+    /// Rotates the primary plane of `crtc` by 180 degrees, if the driver supports it.
+    fn rotate_primary_plane(
+        gpu: &Gpu,
+        resources: &ResourceHandles,
+        crtc: crtc::Handle,
+    ) -> Option<(plane::Handle, property::Handle)> {
+        gpu.set_client_capability(ClientCapability::UniversalPlanes, true)
+            .ok()?;
+
+        for plane in gpu.plane_handles().ok()? {
+            let Ok(info) = gpu.get_plane(plane) else {
+                continue;
+            };
+            if !resources
+                .filter_crtcs(info.possible_crtcs())
+                .contains(&crtc)
+            {
+                continue;
+            }
+
+            let properties = gpu.plane_properties(plane);
+            if properties.get("type").map(|(_, value)| *value) != Some(PLANE_TYPE_PRIMARY) {
+                continue;
+            }
+
+            let (rotation, _) = properties.get("rotation")?;
+            return gpu
+                .set_property(plane, *rotation, ROTATE_180)
+                .ok()
+                .map(|()| (plane, *rotation));
+        }
+
+        None
+    }
+
+    /// Lists all connectors of the device, connected or not.
+    pub(crate) fn connectors(gpu: &Gpu) -> Result<Vec<ConnectorInfo>, DrmError> {
+        let resources = gpu.resource_handles()?;
+        let connectors = resources
+            .connectors()
+            .iter()
+            .flat_map(|handle| gpu.get_connector(*handle, true))
+            .map(|connector| ConnectorInfo::from_drm(&connector))
+            .collect();
+        Ok(connectors)
+    }
+
+    fn select_mode(connector: &connector::Info, config: &DisplayConfig) -> Result<Mode, DrmError> {
+        if config.has_mode() {
+            // prefer the preferred mode, then the highest refresh rate, then the largest size
+            return connector
+                .modes()
+                .iter()
+                .filter(|mode| config.matches(&DisplayMode::from_drm(mode)))
+                .max_by_key(|mode| {
+                    let mode = DisplayMode::from_drm(mode);
+                    (mode.preferred, mode.refresh_rate, mode.width * mode.height)
+                })
+                .copied()
+                .ok_or(DrmError::NoMode);
+        }
+
+        let mut modes: Vec<_> = connector
+            .modes()
+            .iter()
+            .filter(|mode| {
+                mode.mode_type().contains(ModeTypeFlags::PREFERRED) && mode.vrefresh() >= 50
+            })
+            .collect();
+        modes.sort_by(|a, b| {
+            (a.size().0 as u32 * a.size().1 as u32 * a.vrefresh())
+                .cmp(&(b.size().0 as u32 * b.size().1 as u32 * b.vrefresh()))
+        });
+        modes.first().map(|mode| **mode).ok_or(DrmError::NoMode)
+    }
+
     pub(crate) fn size(&self) -> UVec2 {
         UVec2 {
             x: u32::from(self.mode.size().0),
@@ -144,33 +303,178 @@ impl Drm {
         &self.mode
     }
 
+    pub(crate) fn display_mode(&self) -> DisplayMode {
+        DisplayMode::from_drm(&self.mode)
+    }
+
+    pub(crate) fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Rotation that is left to do while rendering.
+    pub(crate) fn screen_rotation(&self) -> Rotation {
+        if self.rotated_plane.is_some() {
+            Rotation::Deg0
+        } else {
+            self.rotation
+        }
+    }
+
+    /// Sets or undoes the hardware rotation, which doesn't survive someone else using the display.
+    pub(crate) fn set_plane_rotated(&self, rotated: bool) -> std::io::Result<()> {
+        if let Some((plane, rotation)) = self.rotated_plane {
+            let value = if rotated { ROTATE_180 } else { ROTATE_0 };
+            self.gpu.set_property(plane, rotation, value)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the display was given back by a panic the game survived, so it has to be taken again.
+    pub(crate) fn take_restored_by_panic() -> bool {
+        RESTORED_BY_PANIC.swap(false, Ordering::Relaxed)
+    }
+
     pub(crate) fn crtc(&self) -> &crtc::Info {
         &self.crtc
     }
+
+    /// Reads pending events and returns whether a page flip on our CRTC completed.
+    /// With `wait` set, this blocks until the device has events to read.
+    pub(crate) fn poll_page_flip(&self, wait: bool) -> Result<bool, DrmError> {
+        let timeout = if wait {
+            PollTimeout::NONE
+        } else {
+            PollTimeout::ZERO
+        };
+        let mut fds = [PollFd::new(self.gpu.as_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, timeout) {
+            Ok(0) | Err(Errno::EINTR) => return Ok(false),
+            Ok(_) => {}
+            Err(e) => return Err(std::io::Error::from(e).into()),
+        }
+
+        let crtc = self.crtc.handle();
+        let flipped = self
+            .gpu
+            .receive_events()?
+            .any(|event| matches!(event, Event::PageFlip(flip) if flip.crtc == crtc));
+        Ok(flipped)
+    }
 }
 
 impl Drop for Drm {
     fn drop(&mut self) {
-        if let Some(state) = &self.original_state {
-            let _ = self.gpu.set_crtc(
-                state.crtc.handle(),
-                state.framebuffer,
-                state.crtc.position(),
-                &state.connectors,
-                state.mode,
-            );
-        }
+        PANIC_RESTORE.lock().take();
+        restore(&self.gpu, self.original_state.as_ref(), self.rotated_plane);
     }
 }
 
+/// Gives the display back to whoever had it before us.
+fn restore(
+    gpu: &Gpu,
+    original_state: Option<&OriginalState>,
+    rotated_plane: Option<(plane::Handle, property::Handle)>,
+) {
+    if let Some((plane, rotation)) = rotated_plane {
+        let _ = gpu.set_property(plane, rotation, ROTATE_0);
+    }
+    if let Some(state) = original_state {
+        let _ = gpu.set_crtc(
+            state.crtc.handle(),
+            state.framebuffer,
+            state.crtc.position(),
+            &state.connectors,
+            state.mode,
+        );
+    }
+}
+
+/// Restores the display and terminal before the previous panic hook runs,
+/// so the panic message ends up on a usable console.
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // the lock might be held by the panicking thread,
+            // and panics of other threads don't end the process unless they abort
+            if let Some(guard) = PANIC_RESTORE.try_lock()
+                && let Some(state) = guard.as_ref()
+                && (cfg!(panic = "abort") || state.thread == std::thread::current().id())
+            {
+                restore(
+                    &state.gpu,
+                    state.original_state.as_ref(),
+                    state.rotated_plane,
+                );
+                crate::vt::restore_after_panic();
+                RESTORED_BY_PANIC.store(true, Ordering::Relaxed);
+            }
+            previous(info);
+        }));
+    });
+}
+
 pub(crate) struct Gpu {
     file: File,
 }
 
 impl Gpu {
-    pub(crate) fn open() -> std::io::Result<Self> {
-        let dir = std::fs::read_dir("/dev/dri")?;
-        for file in dir {
+    /// Opens the selected device. Without a selection,
+    /// this probes for the first device with a connected display.
+    pub(crate) fn open(selector: Option<&DeviceSelector>) -> std::io::Result<Self> {
+        if let Some(DeviceSelector::Path(path)) = selector {
+            return Self::open_path(path);
+        }
+
+        for path in Self::card_paths()? {
+            let Ok(gpu) = Self::open_path(&path) else {
+                continue;
+            };
+
+            let matches = match selector {
+                Some(DeviceSelector::Driver(driver)) => gpu.driver_name() == *driver,
+                _ => gpu.has_connected_connector(),
+            };
+            if matches {
+                log::info!(
+                    "using DRM device {} ({})",
+                    path.display(),
+                    gpu.driver_name()
+                );
+                return Ok(gpu);
+            }
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No valid DRM device found",
+        ))
+    }
+
+    pub(crate) fn open_path(path: &Path) -> std::io::Result<Self> {
+        let file = File::options().write(true).read(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    pub(crate) fn devices() -> std::io::Result<Vec<DeviceInfo>> {
+        let devices = Self::card_paths()?
+            .into_iter()
+            .filter_map(|path| {
+                let gpu = Self::open_path(&path).ok()?;
+                Some(DeviceInfo {
+                    driver: gpu.driver_name(),
+                    connected: gpu.has_connected_connector(),
+                    path,
+                })
+            })
+            .collect();
+        Ok(devices)
+    }
+
+    /// All `card*` nodes in `/dev/dri`, sorted by name.
+    fn card_paths() -> std::io::Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for file in std::fs::read_dir("/dev/dri")? {
             let file = file?;
             if !file.file_type()?.is_char_device() {
                 continue;
@@ -182,13 +486,45 @@ impl Gpu {
             if !name.starts_with("card") {
                 continue;
             }
-            let file = File::options().write(true).read(true).open(file.path())?;
-            return Ok(Self { file });
+            paths.push(file.path());
         }
-        Err(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "No valid DRM device found",
-        ))
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn driver_name(&self) -> String {
+        self.get_driver()
+            .map(|driver| driver.name().to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Names, handles and values of all properties of a plane.
+    fn plane_properties(
+        &self,
+        plane: plane::Handle,
+    ) -> HashMap<String, (property::Handle, property::RawValue)> {
+        let Ok(properties) = self.get_properties(plane) else {
+            return HashMap::new();
+        };
+        properties
+            .iter()
+            .filter_map(|(handle, value)| {
+                let info = self.get_property(*handle).ok()?;
+                let name = info.name().to_string_lossy().into_owned();
+                Some((name, (*handle, *value)))
+            })
+            .collect()
+    }
+
+    fn has_connected_connector(&self) -> bool {
+        let Ok(resources) = self.resource_handles() else {
+            return false;
+        };
+        resources
+            .connectors()
+            .iter()
+            .flat_map(|handle| self.get_connector(*handle, false))
+            .any(|connector| connector.state() == connector::State::Connected)
     }
 }
 
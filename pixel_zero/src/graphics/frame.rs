@@ -46,6 +46,17 @@ impl Frame {
             .push(DrawCommand::Line { start, end, color });
     }
 
+    /// Only draws inside the rectangle from now on.
+    pub fn set_clip(&mut self, position: IVec2, size: UVec2) {
+        self.draw_commands
+            .push(DrawCommand::Clip(Some((position, size))));
+    }
+
+    /// Draws everywhere again after [`Frame::set_clip`].
+    pub fn clear_clip(&mut self) {
+        self.draw_commands.push(DrawCommand::Clip(None));
+    }
+
     pub fn set_clear_color(&mut self, color: Color) {
         self.clear_color = color;
     }
@@ -94,4 +105,44 @@ pub(crate) enum DrawCommand {
         color: Color,
         filled: bool,
     },
+    // position and size, `None` disables clipping
+    Clip(Option<(IVec2, UVec2)>),
+}
+
+impl DrawCommand {
+    /// The same command, moved by `offset`.
+    pub(crate) fn translated(self, offset: IVec2) -> Self {
+        match self {
+            Self::Sprite { sprite, position } => Self::Sprite {
+                sprite,
+                position: position + offset,
+            },
+            Self::Text {
+                font,
+                text,
+                position,
+            } => Self::Text {
+                font,
+                text,
+                position: position + offset,
+            },
+            Self::Line { start, end, color } => Self::Line {
+                start: start + offset,
+                end: end + offset,
+                color,
+            },
+            Self::Rect {
+                position,
+                size,
+                color,
+                filled,
+            } => Self::Rect {
+                position: position + offset,
+                size,
+                color,
+                filled,
+            },
+            Self::Clip(rect) => Self::Clip(rect.map(|(position, size)| (position + offset, size))),
+        }
+    }
 }
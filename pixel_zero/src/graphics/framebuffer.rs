@@ -5,6 +5,7 @@ use thiserror::Error;
 use crate::{
     graphics::{
         color::Color,
+        display::Rotation,
         frame::{DrawCommand, Frame},
         line::Line,
         quad::Quad,
@@ -37,7 +38,11 @@ pub(crate) struct Framebuffer {
 }
 
 impl Framebuffer {
-    pub fn load(gl: &glow::Context, screen_size: UVec2) -> Result<Self, FramebufferError> {
+    pub fn load(
+        gl: &glow::Context,
+        screen_size: UVec2,
+        rotation: Rotation,
+    ) -> Result<Self, FramebufferError> {
         let framebuffer = unsafe { gl.create_framebuffer().map_err(ShaderError::OpenGL)? };
         unsafe {
             gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
@@ -103,8 +108,20 @@ impl Framebuffer {
         shape_shader.bind(gl);
         shape_shader.set_uniform(gl, "u_projection", Uniform::Mat4(projection));
 
+        // the letterbox is fit to the screen as seen after rotating
+        let rotated_size = if rotation.is_portrait() {
+            uvec2(screen_size.y, screen_size.x)
+        } else {
+            screen_size
+        };
+
         screen_shader.bind(gl);
-        screen_shader.set_uniform(gl, "u_screen_size", Uniform::Vec2(screen_size.as_vec2()));
+        screen_shader.set_uniform(gl, "u_screen_size", Uniform::Vec2(rotated_size.as_vec2()));
+        screen_shader.set_uniform(
+            gl,
+            "u_rotation",
+            Uniform::Int(rotation.quarter_turns().cast_signed()),
+        );
         screen_shader.set_uniform(
             gl,
             "u_framebuffer_size",
@@ -156,6 +173,7 @@ impl Framebuffer {
     pub(crate) fn present_frame(&self, gl: &glow::Context, frame: &Frame) {
         self.bind(gl);
 
+        Self::set_clip(gl, None);
         let color = frame.clear_color().f32();
         unsafe {
             gl.clear_color(color.r(), color.g(), color.b(), color.a());
@@ -189,9 +207,11 @@ impl Framebuffer {
                         self.draw_rect(gl, *position, *size, *color);
                     }
                 }
+                DrawCommand::Clip(rect) => Self::set_clip(gl, *rect),
             }
         }
 
+        Self::set_clip(gl, None);
         self.unbind(gl);
 
         self.texture.bind(gl);
@@ -205,6 +225,20 @@ impl Framebuffer {
         Shader::unbind(gl);
     }
 
+    fn set_clip(gl: &glow::Context, rect: Option<(IVec2, UVec2)>) {
+        unsafe {
+            match rect {
+                Some((position, size)) => {
+                    // the projection is flipped, so scissor y starts at the bottom of the frame
+                    let y = HEIGHT.cast_signed() - position.y - size.y.cast_signed();
+                    gl.enable(glow::SCISSOR_TEST);
+                    gl.scissor(position.x, y, size.x.cast_signed(), size.y.cast_signed());
+                }
+                None => gl.disable(glow::SCISSOR_TEST),
+            }
+        }
+    }
+
     fn draw_sprite(&self, gl: &glow::Context, sprite: &Sprite, position: IVec2) {
         self.sprite_shader.bind(gl);
         self.quad.bind_vao(gl);
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use gbm::{BufferObjectFlags, Device, Surface};
 use glam::UVec2;
@@ -9,23 +9,37 @@ pub(crate) struct Gbm {
     size: UVec2,
     device: Device<Arc<Gpu>>,
     surface: Surface<()>,
+    flags: BufferObjectFlags,
+    render_node: bool,
 }
 
 impl Gbm {
-    pub(crate) fn load(drm: &Drm) -> std::io::Result<Self> {
+    /// Allocates buffers on the display device, or on `render_node` if given.
+    pub(crate) fn load(drm: &Drm, render_node: Option<&Path>) -> std::io::Result<Self> {
         let size = drm.size();
-        let device = Device::new(drm.gpu_arc())?;
+        let (gpu, flags) = match render_node {
+            Some(path) => {
+                log::info!("using render node {}", path.display());
+                // the display device has to read these, so keep them in a layout it understands
+                (
+                    Arc::new(Gpu::open_path(path)?),
+                    BufferObjectFlags::RENDERING | BufferObjectFlags::LINEAR,
+                )
+            }
+            None => (
+                drm.gpu_arc(),
+                BufferObjectFlags::SCANOUT | BufferObjectFlags::RENDERING,
+            ),
+        };
+        let device = Device::new(gpu)?;
         log::info!("gbm backend: {}", device.backend_name());
-        let surface = device.create_surface(
-            size.x,
-            size.y,
-            gbm::Format::Xrgb8888,
-            BufferObjectFlags::SCANOUT | BufferObjectFlags::RENDERING,
-        )?;
+        let surface = device.create_surface(size.x, size.y, gbm::Format::Xrgb8888, flags)?;
         Ok(Self {
             size,
             device,
             surface,
+            flags,
+            render_node: render_node.is_some(),
         })
     }
 
@@ -41,13 +55,15 @@ impl Gbm {
         self.size
     }
 
+    /// Whether buffers live on a separate render node instead of the display device.
+    pub(crate) fn uses_render_node(&self) -> bool {
+        self.render_node
+    }
+
     pub(crate) fn init_surface(&mut self, format: gbm::Format) -> std::io::Result<()> {
-        let surface = self.device.create_surface(
-            self.size.x,
-            self.size.y,
-            format,
-            BufferObjectFlags::SCANOUT | BufferObjectFlags::RENDERING,
-        )?;
+        let surface = self
+            .device
+            .create_surface(self.size.x, self.size.y, format, self.flags)?;
         self.surface = surface;
         Ok(())
     }
@@ -5,21 +5,28 @@ use std::{
     time::{Duration, Instant},
 };
 
-use ::drm::control::{Device as _, PageFlipFlags, framebuffer as drmfb};
-use ::gbm::{BufferObject, FrontBufferError};
+use ::drm::{
+    Device as _,
+    control::{Device as _, PageFlipFlags},
+};
+use ::gbm::{FrontBufferError, InvalidFdError};
 use thiserror::Error;
 
 use crate::{
     graphics::{
-        drm::{Drm, DrmError},
+        display::{ConnectorInfo, DeviceInfo, DisplayConfig, DisplayMode, Rotation},
+        drm::{Drm, DrmError, Gpu},
         egl::Egl,
         font::FontError,
         framebuffer::{Framebuffer, FramebufferError},
         gbm::Gbm,
+        scanout::ScanoutBuffer,
         shader::ShaderError,
         texture::TextureError,
     },
+    input::ScreenMapping,
     terminal::TerminalGuard,
+    vt::{VtGuard, VtSwitch},
 };
 
 pub use crate::graphics::{
@@ -27,6 +34,7 @@ pub use crate::graphics::{
 };
 
 pub mod color;
+pub mod display;
 mod drm;
 mod egl;
 pub mod font;
@@ -35,6 +43,7 @@ mod framebuffer;
 mod gbm;
 pub mod line;
 mod quad;
+mod scanout;
 mod shader;
 pub mod sprite;
 mod texture;
@@ -53,10 +62,23 @@ pub enum GraphicsError {
     Framebuffer(#[from] FramebufferError),
     #[error("Front Buffer Error: {0}")]
     FrontBuffer(#[from] FrontBufferError),
+    #[error("Buffer Export Error: {0}")]
+    InvalidFd(#[from] InvalidFdError),
     #[error("Graphics is already loaded")]
     AlreadyLoaded,
 }
 
+/// Outcome of [`Graphics::present_frame_async`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentStatus {
+    /// A page flip to this frame was scheduled.
+    Flipped,
+    /// A page flip is still pending, this frame is shown once it completes.
+    Queued,
+    /// A queued frame was replaced before it reached the screen.
+    Dropped,
+}
+
 pub struct Graphics {
     // this needs to be first to be dropped first
     framebuffer: Framebuffer,
@@ -65,41 +87,55 @@ pub struct Graphics {
     fps_frames: u32,
     fps: u32,
 
-    drm_fb: drmfb::Handle,
-    buffer_object: BufferObject<()>,
+    // currently scanned out
+    front: ScanoutBuffer,
+    // page flip submitted, waiting for completion
+    pending: Option<ScanoutBuffer>,
+    // rendered while a flip was pending
+    queued: Option<ScanoutBuffer>,
 
     egl: Egl,
     gbm: Gbm,
     drm: Drm,
 
+    vt_guard: Option<VtGuard>,
     _terminal_guard: TerminalGuard,
 }
 
+const VT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 pub(crate) static GRAPHICS_LOADED: AtomicBool = AtomicBool::new(false);
 impl Graphics {
+    /// Loads graphics with the display config from the environment,
+    /// see [`DisplayConfig::from_env`].
     pub fn load() -> Result<Self, GraphicsError> {
+        Self::load_with(&DisplayConfig::from_env())
+    }
+
+    pub fn load_with(config: &DisplayConfig) -> Result<Self, GraphicsError> {
         if GRAPHICS_LOADED.swap(true, Ordering::Relaxed) {
             return Err(GraphicsError::AlreadyLoaded);
         }
 
         let terminal_guard = TerminalGuard::new().map_err(std::io::Error::from)?;
 
-        let drm = Drm::load()?;
-        let mut gbm = Gbm::load(&drm)?;
+        let drm = Drm::load(config)?;
+        let mut gbm = Gbm::load(&drm, config.render_node.as_deref())?;
         let egl = Egl::load(&mut gbm)?;
 
-        let buffer_object = unsafe { gbm.surface().lock_front_buffer() }?;
-        let bpp = buffer_object.bpp();
-        let drm_fb = drm.gpu().add_framebuffer(&buffer_object, bpp, bpp)?;
-        drm.gpu().set_crtc(
-            drm.crtc().handle(),
-            Some(drm_fb),
-            (0, 0),
-            &[drm.connector().handle()],
-            Some(*drm.mode()),
-        )?;
+        let front = ScanoutBuffer::lock(&drm, &gbm)?;
+        Self::set_crtc(&drm, &front)?;
 
-        let framebuffer = Framebuffer::load(egl.gl(), drm.size())?;
+        // without a virtual terminal, e.g. over ssh, nobody can switch away from us
+        let vt_guard = VtGuard::new()
+            .inspect_err(|e| log::warn!("virtual terminal switching unavailable: {e}"))
+            .ok();
+
+        let framebuffer = Framebuffer::load(egl.gl(), drm.size(), drm.screen_rotation())?;
+        ScreenMapping::set_current(Some(ScreenMapping {
+            size: drm.display_mode().size(),
+            rotation: drm.rotation(),
+        }));
         let frame_start = Instant::now();
         let fps_timer = frame_start;
 
@@ -109,15 +145,47 @@ impl Graphics {
             fps_timer,
             fps_frames: 0,
             fps: 0,
-            drm_fb,
-            buffer_object,
+            front,
+            pending: None,
+            queued: None,
             drm,
             gbm,
             egl,
+            vt_guard,
             _terminal_guard: terminal_guard,
         })
     }
 
+    /// Lists the DRM devices, to pick one for [`Graphics::load_with`].
+    pub fn devices() -> Result<Vec<DeviceInfo>, GraphicsError> {
+        Ok(Gpu::devices()?)
+    }
+
+    /// Lists the connectors of the display device selected by `config`,
+    /// to pick one for [`Graphics::load_with`].
+    pub fn connectors(config: &DisplayConfig) -> Result<Vec<ConnectorInfo>, GraphicsError> {
+        let gpu = Gpu::open(config.device.as_ref())?;
+        Ok(Drm::connectors(&gpu)?)
+    }
+
+    /// Name of the connector in use, like `HDMI-A-1`.
+    #[must_use]
+    pub fn connector_name(&self) -> String {
+        self.drm.connector().to_string()
+    }
+
+    /// Rotation of the picture on the panel.
+    #[must_use]
+    pub fn rotation(&self) -> Rotation {
+        self.drm.rotation()
+    }
+
+    /// The mode the display is driven with.
+    #[must_use]
+    pub fn display_mode(&self) -> DisplayMode {
+        self.drm.display_mode()
+    }
+
     pub fn load_sprite(&self, path: impl AsRef<Path>) -> Result<Sprite, TextureError> {
         Sprite::load(self.egl.gl(), path)
     }
@@ -138,35 +206,193 @@ impl Graphics {
         Font::load_read(self.egl.gl(), reader)
     }
 
-    const FRAME_DURATION: Duration = Duration::from_micros(16667);
+    /// Renders the frame and blocks until it is on screen.
+    ///
+    /// While switched to another virtual terminal, this blocks until switched back.
     pub fn present_frame(&mut self, frame: &Frame) -> Result<(), GraphicsError> {
+        self.retake_after_panic()?;
+        self.handle_vt_switch()?;
+        self.drop_queued()?;
+        while self.pending.is_some() {
+            self.dispatch_page_flip(true)?;
+        }
+
+        let buffer = self.render(frame)?;
+        self.page_flip(buffer)?;
+        while self.pending.is_some() {
+            self.dispatch_page_flip(true)?;
+        }
+
+        self.frame_start = Instant::now();
+        self.update_fps();
+
+        Ok(())
+    }
+
+    /// Renders the frame without waiting for the display.
+    ///
+    /// At most two buffers are in flight: one waiting for its page flip, and one queued behind it.
+    /// Completed flips are picked up by polling, so the next frame can be rendered right away.
+    ///
+    /// While switched to another virtual terminal, this blocks until switched back.
+    pub fn present_frame_async(&mut self, frame: &Frame) -> Result<PresentStatus, GraphicsError> {
+        self.retake_after_panic()?;
+        self.handle_vt_switch()?;
+        self.dispatch_page_flip(false)?;
+
+        // free the queued buffer before rendering, so we never hold more than three
+        let dropped = self.drop_queued()?;
+
+        let buffer = self.render(frame)?;
+        let status = if self.pending.is_none() {
+            self.page_flip(buffer)?;
+            PresentStatus::Flipped
+        } else {
+            self.queued = Some(buffer);
+            if dropped {
+                PresentStatus::Dropped
+            } else {
+                PresentStatus::Queued
+            }
+        };
+
+        self.frame_start = Instant::now();
+        self.update_fps();
+
+        Ok(status)
+    }
+
+    fn set_crtc(drm: &Drm, buffer: &ScanoutBuffer) -> Result<(), GraphicsError> {
+        drm.gpu().set_crtc(
+            drm.crtc().handle(),
+            Some(buffer.framebuffer()),
+            (0, 0),
+            &[drm.connector().handle()],
+            Some(*drm.mode()),
+        )?;
+        Ok(())
+    }
+
+    /// Takes the display and terminal back, after a caught panic on this thread restored them.
+    fn retake_after_panic(&mut self) -> Result<(), GraphicsError> {
+        if !Drm::take_restored_by_panic() {
+            return Ok(());
+        }
+
+        log::info!("recovered from a panic, taking the display back");
+        Self::set_crtc(&self.drm, &self.front)?;
+        self.drm.set_plane_rotated(true)?;
+        if let Some(vt_guard) = &self.vt_guard {
+            vt_guard.retake()?;
+        }
+        Ok(())
+    }
+
+    /// Gives up the display when switching to another virtual terminal,
+    /// and waits until switched back before taking it again.
+    fn handle_vt_switch(&mut self) -> Result<(), GraphicsError> {
+        let Some(vt_guard) = self.vt_guard.take() else {
+            return Ok(());
+        };
+        let result = self.switch_vt(&vt_guard);
+        self.vt_guard = Some(vt_guard);
+        result
+    }
+
+    fn switch_vt(&mut self, vt_guard: &VtGuard) -> Result<(), GraphicsError> {
+        if vt_guard.poll() != Some(VtSwitch::Release) {
+            return Ok(());
+        }
+
+        while self.pending.is_some() {
+            self.dispatch_page_flip(true)?;
+        }
+        self.drop_queued()?;
+        // so the console isn't upside down
+        if let Err(e) = self.drm.set_plane_rotated(false) {
+            log::warn!("failed to undo the display rotation: {e}");
+        }
+        self.drm.gpu().release_master_lock()?;
+
+        vt_guard.acknowledge_release()?;
+        log::info!("switched away from virtual terminal, pausing");
+
+        while vt_guard.poll() != Some(VtSwitch::Acquire) {
+            std::thread::sleep(VT_POLL_INTERVAL);
+        }
+
+        self.drm.gpu().acquire_master_lock()?;
+        Self::set_crtc(&self.drm, &self.front)?;
+        if let Err(e) = self.drm.set_plane_rotated(true) {
+            log::warn!("failed to rotate the display again: {e}");
+        }
+        vt_guard.acknowledge_acquire()?;
+        log::info!("switched back to virtual terminal, resuming");
+
+        // time spent in the background should not count towards the fps
+        self.frame_start = Instant::now();
+        self.fps_timer = self.frame_start;
+        self.fps_frames = 0;
+
+        Ok(())
+    }
+
+    fn render(&mut self, frame: &Frame) -> Result<ScanoutBuffer, GraphicsError> {
         self.framebuffer.present_frame(self.egl.gl(), frame);
 
         self.egl
             .instance()
             .swap_buffers(self.egl.display(), self.egl.surface())?;
 
-        let buffer_object = unsafe { self.gbm.surface().lock_front_buffer() }?;
-        let bpp = buffer_object.bpp();
-        let drm_fb = self.drm.gpu().add_framebuffer(&buffer_object, bpp, bpp)?;
+        ScanoutBuffer::lock(&self.drm, &self.gbm)
+    }
 
-        self.drm
-            .gpu()
-            .page_flip(self.drm.crtc().handle(), drm_fb, PageFlipFlags::EVENT, None)?;
-        let _events = self.drm.gpu().receive_events()?;
+    fn page_flip(&mut self, buffer: ScanoutBuffer) -> Result<(), GraphicsError> {
+        if let Err(e) = self.drm.gpu().page_flip(
+            self.drm.crtc().handle(),
+            buffer.framebuffer(),
+            PageFlipFlags::EVENT,
+            None,
+        ) {
+            self.release(buffer)?;
+            return Err(e.into());
+        }
+        self.pending = Some(buffer);
+        Ok(())
+    }
 
-        self.drm.gpu().destroy_framebuffer(self.drm_fb)?;
+    /// Handles a completed page flip, and submits the queued buffer if there is one.
+    fn dispatch_page_flip(&mut self, wait: bool) -> Result<(), GraphicsError> {
+        if self.pending.is_none() || !self.drm.poll_page_flip(wait)? {
+            return Ok(());
+        }
 
-        self.buffer_object = buffer_object;
-        self.drm_fb = drm_fb;
+        if let Some(pending) = self.pending.take() {
+            let previous = std::mem::replace(&mut self.front, pending);
+            self.release(previous)?;
+        }
 
-        // std::thread::sleep(Self::FRAME_DURATION.saturating_sub(self.frame_start.elapsed()));
-        self.frame_start = Instant::now();
-        self.update_fps();
+        if let Some(queued) = self.queued.take() {
+            self.page_flip(queued)?;
+        }
 
         Ok(())
     }
 
+    /// Returns whether a buffer was queued.
+    fn drop_queued(&mut self) -> Result<bool, GraphicsError> {
+        let Some(queued) = self.queued.take() else {
+            return Ok(false);
+        };
+        self.release(queued)?;
+        Ok(true)
+    }
+
+    fn release(&self, buffer: ScanoutBuffer) -> Result<(), GraphicsError> {
+        buffer.destroy(self.drm.gpu())?;
+        Ok(())
+    }
+
     #[must_use]
     pub fn fps(&self) -> u32 {
         self.fps
@@ -185,8 +411,23 @@ impl Graphics {
 
 impl Drop for Graphics {
     fn drop(&mut self) {
-        if let Err(e) = self.drm.gpu().destroy_framebuffer(self.drm_fb) {
-            log::error!("failed to destroy framebuffer on Graphics drop: {e}");
+        ScreenMapping::set_current(None);
+        while self.pending.is_some() {
+            if let Err(e) = self.dispatch_page_flip(true) {
+                log::error!("failed to wait for page flip on Graphics drop: {e}");
+                break;
+            }
+        }
+
+        let buffers = [
+            Some(&self.front),
+            self.pending.as_ref(),
+            self.queued.as_ref(),
+        ];
+        for buffer in buffers.into_iter().flatten() {
+            if let Err(e) = buffer.destroy(self.drm.gpu()) {
+                log::error!("failed to destroy framebuffer on Graphics drop: {e}");
+            }
         }
         GRAPHICS_LOADED.store(false, Ordering::Relaxed);
     }
@@ -0,0 +1,96 @@
+use std::os::fd::AsFd as _;
+
+use drm::{
+    buffer::{Buffer, DrmFourcc, Handle},
+    control::{Device as _, framebuffer},
+};
+use gbm::BufferObject;
+
+use crate::graphics::{GraphicsError, drm::Drm, drm::Gpu, gbm::Gbm};
+
+/// A locked GBM buffer together with the DRM framebuffer that scans it out.
+pub(crate) struct ScanoutBuffer {
+    framebuffer: framebuffer::Handle,
+    // only set when the buffer was rendered on a separate render node
+    imported: Option<Handle>,
+    _buffer_object: BufferObject<()>,
+}
+
+impl ScanoutBuffer {
+    pub(crate) fn lock(drm: &Drm, gbm: &Gbm) -> Result<Self, GraphicsError> {
+        let buffer_object = unsafe { gbm.surface().lock_front_buffer() }?;
+        let bpp = buffer_object.bpp();
+
+        if !gbm.uses_render_node() {
+            let framebuffer = drm.gpu().add_framebuffer(&buffer_object, bpp, bpp)?;
+            return Ok(Self {
+                framebuffer,
+                imported: None,
+                _buffer_object: buffer_object,
+            });
+        }
+
+        // the buffer belongs to another device, so it is shared with the display device as a dma-buf
+        let fd = buffer_object.fd()?;
+        let handle = drm.gpu().prime_fd_to_buffer(fd.as_fd())?;
+        let prime = PrimeBuffer {
+            handle,
+            size: (buffer_object.width(), buffer_object.height()),
+            format: buffer_object.format(),
+            pitch: buffer_object.stride(),
+        };
+
+        let framebuffer = match drm.gpu().add_framebuffer(&prime, bpp, bpp) {
+            Ok(framebuffer) => framebuffer,
+            Err(e) => {
+                let _ = drm.gpu().close_buffer(handle);
+                return Err(e.into());
+            }
+        };
+
+        Ok(Self {
+            framebuffer,
+            imported: Some(handle),
+            _buffer_object: buffer_object,
+        })
+    }
+
+    pub(crate) fn framebuffer(&self) -> framebuffer::Handle {
+        self.framebuffer
+    }
+
+    /// Destroys the framebuffer, the buffer object is released on drop.
+    pub(crate) fn destroy(&self, gpu: &Gpu) -> std::io::Result<()> {
+        gpu.destroy_framebuffer(self.framebuffer)?;
+        if let Some(handle) = self.imported {
+            gpu.close_buffer(handle)?;
+        }
+        Ok(())
+    }
+}
+
+/// A buffer imported from another device through PRIME.
+struct PrimeBuffer {
+    handle: Handle,
+    size: (u32, u32),
+    format: DrmFourcc,
+    pitch: u32,
+}
+
+impl Buffer for PrimeBuffer {
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn format(&self) -> DrmFourcc {
+        self.format
+    }
+
+    fn pitch(&self) -> u32 {
+        self.pitch
+    }
+
+    fn handle(&self) -> Handle {
+        self.handle
+    }
+}
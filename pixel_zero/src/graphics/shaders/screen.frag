@@ -2,6 +2,7 @@ precision mediump float;
 
 varying vec2 v_texcoord;
 
+// already rotated, like the texcoords
 uniform vec2 u_screen_size;
 uniform vec2 u_framebuffer_size;
 uniform sampler2D u_texture;
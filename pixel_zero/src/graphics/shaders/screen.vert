@@ -3,8 +3,22 @@ attribute vec2 a_texcoord;
 
 varying vec2 v_texcoord;
 
+// clockwise quarter turns
+uniform int u_rotation;
+
+vec2 rotate(vec2 uv) {
+    if (u_rotation == 1) {
+        return vec2(1.0 - uv.y, uv.x);
+    } else if (u_rotation == 2) {
+        return vec2(1.0 - uv.x, 1.0 - uv.y);
+    } else if (u_rotation == 3) {
+        return vec2(uv.y, 1.0 - uv.x);
+    }
+    return uv;
+}
+
 void main() {
     vec2 ndc = a_position * 2.0 - 1.0;
     gl_Position = vec4(ndc, 0.0, 1.0);
-    v_texcoord = a_texcoord;
+    v_texcoord = rotate(a_texcoord);
 }
@@ -18,6 +18,12 @@ impl Sprite {
         Ok(Self(Arc::new(inner)))
     }
 
+    /// Size in pixels.
+    #[must_use]
+    pub fn size(&self) -> UVec2 {
+        self.texture().size()
+    }
+
     pub(crate) fn texture(&self) -> &Texture {
         &self.0.texture
     }
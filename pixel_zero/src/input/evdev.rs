@@ -0,0 +1,932 @@
+use std::{
+    fs::File,
+    io::Read as _,
+    os::{
+        fd::AsRawFd,
+        unix::fs::{FileTypeExt, OpenOptionsExt},
+    },
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use bytemuck::{AnyBitPattern, NoUninit};
+use glam::{Vec2, vec2};
+use nix::{
+    ioctl_read, ioctl_read_buf,
+    libc::c_long,
+    request_code_read,
+    sys::inotify::{AddWatchFlags, InitFlags, Inotify},
+};
+use strum::EnumCount as _;
+
+use crate::input::{
+    Binding, Button, ButtonEvent, ButtonMap, ButtonProfiles, ConnectionEvent, DeviceId,
+    InputSource, RawPress, Stick, Trigger, event_time,
+    keys::*,
+    map::AxisDirection,
+    player::PlayerSlots,
+    rumble::Rumble,
+    system::SystemEvent,
+    text::{self, TextInput},
+};
+
+const INPUT_DIR: &str = "/dev/input";
+// only used when inotify is not available
+const SCAN_INTERVAL: Duration = Duration::from_secs(5);
+const KEY_STATE_BYTES: usize = 1024;
+const EVENT_BUFFER_SIZE: usize = 64;
+// how far an axis has to be moved from its center to count as a button press
+const AXIS_THRESHOLD: f32 = 0.25;
+// more fingers than this are ignored
+const MAX_TOUCHES: usize = 10;
+
+ioctl_read!(device_id, b'E', 0x02, InputId);
+ioctl_read_buf!(key_state, b'E', 0x18, u8);
+ioctl_read_buf!(key_bits, b'E', 0x20 + EV_KEY, u8);
+ioctl_read_buf!(abs_bits, b'E', 0x20 + EV_ABS, u8);
+ioctl_read_buf!(rel_bits, b'E', 0x20 + EV_REL, u8);
+ioctl_read_buf!(prop_bits, b'E', 0x09, u8);
+
+/// Reads keyboards, controllers, touchscreens and mice from `/dev/input`.
+pub(crate) struct EvdevSource {
+    devices: Vec<Device>,
+    slots: PlayerSlots,
+    watcher: Option<Inotify>,
+    last_scanned: Instant,
+    connection_events: Vec<ConnectionEvent>,
+    // devices found at startup are reported by the first update
+    polled: bool,
+    raw_presses: Vec<RawPress>,
+    text_input: Vec<TextInput>,
+    system_events: Vec<SystemEvent>,
+    // relative mouse movement since the last poll
+    mouse_motion: Option<Vec2>,
+    profiles: ButtonProfiles,
+}
+
+impl EvdevSource {
+    pub(crate) fn new(profiles: ButtonProfiles) -> Self {
+        let mut source = Self {
+            devices: Vec::new(),
+            slots: PlayerSlots::default(),
+            watcher: Self::watch_devices(),
+            last_scanned: Instant::now(),
+            connection_events: Vec::new(),
+            polled: false,
+            raw_presses: Vec::new(),
+            text_input: Vec::new(),
+            system_events: Vec::new(),
+            mouse_motion: None,
+            profiles,
+        };
+        source.scan_devices();
+        log::info!("found {} input devices", source.devices.len());
+        source
+    }
+
+    pub(crate) fn profiles(&self) -> &ButtonProfiles {
+        &self.profiles
+    }
+
+    pub(crate) fn set_profiles(&mut self, profiles: ButtonProfiles) {
+        for device in &mut self.devices {
+            device.map = profiles.map_for(device.id).clone();
+        }
+        self.profiles = profiles;
+    }
+
+    pub(crate) fn connection_events(&self) -> &[ConnectionEvent] {
+        &self.connection_events
+    }
+
+    pub(crate) fn raw_presses(&self) -> &[RawPress] {
+        &self.raw_presses
+    }
+
+    /// Whether a physical input is held on any device, or was pressed since the last poll.
+    pub(crate) fn binding_active(&self, binding: Binding) -> bool {
+        self.devices.iter().any(|device| device.is_active(binding))
+            || self
+                .raw_presses
+                .iter()
+                .any(|press| press.binding == binding)
+    }
+
+    /// Text typed since the last poll, on all keyboards.
+    pub(crate) fn text_input(&self) -> &[TextInput] {
+        &self.text_input
+    }
+
+    /// System keys pressed since the last poll.
+    pub(crate) fn system_events(&self) -> &[SystemEvent] {
+        &self.system_events
+    }
+
+    pub(crate) fn player(&self, player: usize) -> Option<&Device> {
+        self.devices
+            .iter()
+            .find(|device| device.slot == Some(player))
+    }
+
+    pub(crate) fn rumble(&mut self, player: usize, strength: f32, duration: Duration) {
+        let Some(device) = self
+            .devices
+            .iter_mut()
+            .find(|device| device.slot == Some(player))
+        else {
+            return;
+        };
+        let Some(rumble) = &mut device.rumble else {
+            return;
+        };
+
+        if let Err(e) = rumble.play(&device.file, strength, duration) {
+            log::warn!("failed to rumble {}: {e}", device.id);
+            device.rumble = None;
+        }
+    }
+
+    /// With multiple devices, the one pushed furthest wins.
+    pub(crate) fn stick(&self, stick: Stick) -> Vec2 {
+        self.devices
+            .iter()
+            .filter_map(|device| device.stick(stick))
+            .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn trigger(&self, trigger: Trigger) -> f32 {
+        self.devices
+            .iter()
+            .filter_map(|device| device.trigger(trigger))
+            .fold(0.0, f32::max)
+    }
+
+    /// Fingers on all touchscreens, positions are normalized to `0.0..=1.0`.
+    pub(crate) fn touches(&self) -> impl Iterator<Item = (i32, Vec2)> + '_ {
+        self.devices.iter().flat_map(Device::touches)
+    }
+
+    /// Mouse movement since the last poll, in mouse units.
+    pub(crate) fn take_mouse_motion(&mut self) -> Option<Vec2> {
+        self.mouse_motion.take()
+    }
+
+    pub(crate) fn mouse_pressed(&self) -> bool {
+        self.devices.iter().any(Device::mouse_pressed)
+    }
+
+    /// Watches the input directory for added and removed devices.
+    fn watch_devices() -> Option<Inotify> {
+        let watcher = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)
+            .inspect_err(|e| log::warn!("failed to init inotify, rescanning periodically: {e}"))
+            .ok()?;
+
+        // udev sets permissions after creating the node, so opening is retried on attribute changes
+        watcher
+            .add_watch(
+                INPUT_DIR,
+                AddWatchFlags::IN_CREATE | AddWatchFlags::IN_ATTRIB | AddWatchFlags::IN_DELETE,
+            )
+            .inspect_err(|e| {
+                log::warn!("failed to watch {INPUT_DIR}, rescanning periodically: {e}")
+            })
+            .ok()?;
+        Some(watcher)
+    }
+
+    /// Opens devices that are not open yet.
+    fn scan_devices(&mut self) {
+        for path in Self::device_paths() {
+            self.add_device(path);
+        }
+    }
+
+    /// Handles inotify events, or rescans if inotify is not available.
+    fn update_devices(&mut self, events: &mut Vec<ButtonEvent>) {
+        let Some(watcher) = &self.watcher else {
+            if self.last_scanned.elapsed() > SCAN_INTERVAL {
+                self.scan_devices();
+                self.last_scanned = Instant::now();
+            }
+            return;
+        };
+
+        let watch_events = match watcher.read_events() {
+            Ok(events) => events,
+            Err(nix::errno::Errno::EAGAIN) => return,
+            Err(e) => {
+                log::warn!("failed to read inotify events: {e}");
+                return;
+            }
+        };
+
+        for event in watch_events {
+            let Some(name) = event.name else {
+                continue;
+            };
+            if !name.to_string_lossy().starts_with("event") {
+                continue;
+            }
+
+            let path = Path::new(INPUT_DIR).join(name);
+            if event.mask.contains(AddWatchFlags::IN_DELETE) {
+                if let Some(index) = self.devices.iter().position(|device| device.path == path) {
+                    let mut device = self.devices.remove(index);
+                    self.disconnect(&mut device, events);
+                }
+            } else {
+                self.add_device(path);
+            }
+        }
+    }
+
+    fn add_device(&mut self, path: PathBuf) {
+        if self.devices.iter().any(|device| device.path == path) {
+            return;
+        }
+
+        let Some(mut device) = Device::open(path, &self.profiles) else {
+            return;
+        };
+
+        if device.is_controller() {
+            device.slot = self.slots.connect(device.id);
+            if let Some(slot) = device.slot {
+                log::info!("{} connected as player {}", device.id, slot + 1);
+            }
+        }
+        self.connection_events.push(ConnectionEvent::Connected {
+            device: device.id,
+            player: device.slot,
+        });
+        self.devices.push(device);
+    }
+
+    /// Releases the buttons still held on a removed device, so they don't stick.
+    fn disconnect(&mut self, device: &mut Device, events: &mut Vec<ButtonEvent>) {
+        device.release_buttons(events);
+        if let Some(slot) = device.slot {
+            log::info!("player {} disconnected", slot + 1);
+            self.slots.disconnect(slot);
+        }
+        self.connection_events.push(ConnectionEvent::Disconnected {
+            device: device.id,
+            player: device.slot,
+        });
+    }
+
+    fn device_paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        let Ok(entries) = std::fs::read_dir(INPUT_DIR) else {
+            return paths;
+        };
+
+        for entry in entries {
+            let Ok(entry) = entry else {
+                continue;
+            };
+
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if !file_type.is_char_device() {
+                continue;
+            }
+
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+
+            if !file_name.starts_with("event") {
+                continue;
+            }
+
+            paths.push(entry.path());
+        }
+
+        // keeps player order stable between runs
+        paths.sort();
+        paths
+    }
+}
+
+impl InputSource for EvdevSource {
+    fn poll(&mut self, events: &mut Vec<ButtonEvent>) -> [bool; Button::COUNT] {
+        if std::mem::replace(&mut self.polled, true) {
+            self.connection_events.clear();
+        }
+        self.update_devices(events);
+
+        self.raw_presses.clear();
+        let mut removed: Vec<_> = self
+            .devices
+            .extract_if(.., |device| {
+                let start = events.len();
+                let connected = device.read_events(events, &mut self.raw_presses);
+                device.update_frame_state(&events[start..]);
+                !connected
+            })
+            .collect();
+        for device in &mut removed {
+            self.disconnect(device, events);
+        }
+
+        self.text_input.clear();
+        self.system_events.clear();
+        let mut state = [false; Button::COUNT];
+        for device in &mut self.devices {
+            for (current, pressed) in state.iter_mut().zip(device.buttons) {
+                *current |= pressed;
+            }
+            self.text_input.append(&mut device.text_input);
+            self.system_events.append(&mut device.system_events);
+            if let Some(motion) = device.mouse_motion.take() {
+                *self.mouse_motion.get_or_insert_default() += motion;
+            }
+        }
+        state
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Device {
+    file: File,
+    path: PathBuf,
+    kind: DeviceKind,
+    pub(crate) id: DeviceId,
+    map: ButtonMap,
+    pub(crate) slot: Option<usize>,
+    // keys the device has
+    supported_keys: [u8; KEY_STATE_BYTES],
+    keys: [u8; KEY_STATE_BYTES],
+    abs: [Option<InputAbsInfo>; ABS_COUNT],
+    // button state as of the last `SYN_REPORT`
+    pub(crate) buttons: [bool; Button::COUNT],
+    // changes since the last `Input::update`
+    pub(crate) pressed: [bool; Button::COUNT],
+    pub(crate) released: [bool; Button::COUNT],
+    // events were lost, skip everything until the next `SYN_REPORT` and resync
+    dropped: bool,
+    rumble: Option<Rumble>,
+    // text typed since the last `Input::update`
+    text_input: Vec<TextInput>,
+    caps_lock: bool,
+    // system keys pressed since the last `Input::update`
+    system_events: Vec<SystemEvent>,
+    // `None` for devices that aren't touchscreens
+    touch: Option<TouchState>,
+    is_mouse: bool,
+    mouse_motion: Option<Vec2>,
+}
+
+impl Device {
+    fn open(path: PathBuf, profiles: &ButtonProfiles) -> Option<Self> {
+        // writing is only needed for rumble, so fall back to read only
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .custom_flags(nix::libc::O_NONBLOCK)
+            .open(&path)
+            .or_else(|_| {
+                File::options()
+                    .read(true)
+                    .custom_flags(nix::libc::O_NONBLOCK)
+                    .open(&path)
+            })
+            .ok()?;
+        let kind = DeviceKind::from_device(&file)?;
+        let rumble = Rumble::new(&file);
+
+        let mut supported_keys = [0; KEY_STATE_BYTES];
+        if kind.has_keys() {
+            let _ = unsafe { key_bits(file.as_raw_fd(), &mut supported_keys) };
+        }
+
+        let touch = TouchState::from_device(&file, &supported_keys);
+        let is_mouse = touch.is_none() && Self::has_bit(&supported_keys, BTN_LEFT) && {
+            let mut bits = [0u8; 1];
+            unsafe { rel_bits(file.as_raw_fd(), &mut bits) }.is_ok()
+                && Self::has_bit(&bits, REL_X)
+                && Self::has_bit(&bits, REL_Y)
+        };
+
+        let mut input_id = InputId::default();
+        let _ = unsafe { device_id(file.as_raw_fd(), &raw mut input_id) };
+        let id = DeviceId {
+            vendor: input_id.vendor,
+            product: input_id.product,
+        };
+        if profiles.devices.contains_key(&id) {
+            log::info!("using button profile for {id}");
+        }
+
+        let mut device = Self {
+            file,
+            path,
+            kind,
+            id,
+            map: profiles.map_for(id).clone(),
+            slot: None,
+            supported_keys,
+            keys: [0; KEY_STATE_BYTES],
+            abs: [None; ABS_COUNT],
+            buttons: [false; Button::COUNT],
+            pressed: [false; Button::COUNT],
+            released: [false; Button::COUNT],
+            dropped: false,
+            rumble,
+            text_input: Vec::new(),
+            caps_lock: false,
+            system_events: Vec::new(),
+            touch,
+            is_mouse,
+            mouse_motion: None,
+        };
+        device.sync();
+        device.buttons = device.map_buttons();
+        Some(device)
+    }
+
+    /// Whether any mapped input exists on the device.
+    /// Other devices, like power buttons or sensors, don't get a player slot.
+    /// Only gamepad buttons and sticks count, so keyboards and system key devices
+    /// like volume buttons don't take player slots.
+    fn is_controller(&self) -> bool {
+        const STICK_AXES: [u16; 6] = [ABS_X, ABS_Y, ABS_RX, ABS_RY, ABS_HAT0X, ABS_HAT0Y];
+
+        self.touch.is_none()
+            && self
+                .map
+                .iter()
+                .filter(|(button, _)| !button.is_extended())
+                .any(|(_, binding)| match binding {
+                    Binding::Key(code) => {
+                        ((BTN_JOYSTICK..=BTN_GAMEPAD_LAST).contains(&code)
+                            || (BTN_DPAD_UP..=BTN_DPAD_RIGHT).contains(&code))
+                            && Self::has_bit(&self.supported_keys, code)
+                    }
+                    Binding::Axis(code, _) => {
+                        STICK_AXES.contains(&code)
+                            && self.abs.get(code as usize).is_some_and(Option::is_some)
+                    }
+                })
+    }
+
+    fn update_frame_state(&mut self, events: &[ButtonEvent]) {
+        self.pressed = [false; Button::COUNT];
+        self.released = [false; Button::COUNT];
+        for event in events {
+            if event.pressed {
+                self.pressed[event.button.index()] = true;
+            } else {
+                self.released[event.button.index()] = true;
+            }
+        }
+    }
+
+    /// Reads the full key and axis state from the device.
+    fn sync(&mut self) {
+        if self.kind.has_keys()
+            && unsafe { key_state(self.file.as_raw_fd(), &mut self.keys) }.is_err()
+        {
+            self.keys = [0; KEY_STATE_BYTES];
+        }
+
+        if self.kind.has_abs() {
+            let mut bits = [0u8; ABS_COUNT / 8];
+            if unsafe { abs_bits(self.file.as_raw_fd(), &mut bits) }.is_err() {
+                return;
+            }
+            for axis in 0..ABS_COUNT as u16 {
+                self.abs[axis as usize] = if Self::has_bit(&bits, axis) {
+                    read_abs_info(&self.file, axis)
+                } else {
+                    None
+                };
+            }
+        }
+
+        // lifted fingers may have been lost, they come back with the next touch
+        if let Some(touch) = &mut self.touch {
+            touch.clear();
+        }
+    }
+
+    /// Reads all pending events, and appends the resulting button changes to `events`.
+    /// Returns `false` if the device is gone.
+    fn read_events(&mut self, events: &mut Vec<ButtonEvent>, raw: &mut Vec<RawPress>) -> bool {
+        let mut buffer = [InputEvent::default(); EVENT_BUFFER_SIZE];
+        loop {
+            let bytes = bytemuck::cast_slice_mut::<InputEvent, u8>(&mut buffer);
+            let count = match self.file.read(bytes) {
+                Ok(read) => read / size_of::<InputEvent>(),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::info!("input device removed: {e}");
+                    return false;
+                }
+            };
+
+            for event in &buffer[..count] {
+                self.process(event, events, raw);
+            }
+
+            if count < EVENT_BUFFER_SIZE {
+                return true;
+            }
+        }
+    }
+
+    fn process(
+        &mut self,
+        event: &InputEvent,
+        events: &mut Vec<ButtonEvent>,
+        raw: &mut Vec<RawPress>,
+    ) {
+        match (event.kind, event.code) {
+            (EV_SYN, SYN_DROPPED) => self.dropped = true,
+            (EV_SYN, SYN_REPORT) => {
+                if self.dropped {
+                    self.dropped = false;
+                    self.sync();
+                }
+                self.report(event.time(), events);
+            }
+            _ if self.dropped => {}
+            (EV_KEY, code) => {
+                // 2 is a key repeat, which doesn't change the state
+                match event.value {
+                    0 => Self::set_bit(&mut self.keys, code, false),
+                    1 => {
+                        Self::set_bit(&mut self.keys, code, true);
+                        self.caps_lock ^= code == KEY_CAPSLOCK;
+                        raw.push(RawPress {
+                            device: self.id,
+                            binding: Binding::Key(code),
+                        });
+                    }
+                    _ => {}
+                }
+
+                // repeats type again, like in a terminal
+                if event.value != 0 {
+                    self.system_events
+                        .extend(SystemEvent::from_key(code, event.value == 2));
+                    let shift = Self::has_bit(&self.keys, KEY_LEFTSHIFT)
+                        || Self::has_bit(&self.keys, KEY_RIGHTSHIFT);
+                    self.text_input
+                        .extend(text::translate(code, shift, self.caps_lock));
+                }
+            }
+            (EV_REL, code @ (REL_X | REL_Y)) if self.is_mouse => {
+                let motion = self.mouse_motion.get_or_insert_default();
+                if code == REL_X {
+                    motion.x += event.value as f32;
+                } else {
+                    motion.y += event.value as f32;
+                }
+            }
+            (EV_ABS, code) => {
+                if let Some(touch) = &mut self.touch {
+                    touch.process(code, event.value);
+                }
+                if let Some(Some(info)) = self.abs.get_mut(code as usize) {
+                    let before = Self::axis_direction(info);
+                    info.value = event.value;
+                    let after = Self::axis_direction(info);
+                    if let Some(direction) = after
+                        && before != after
+                        && self.touch.is_none()
+                    {
+                        raw.push(RawPress {
+                            device: self.id,
+                            binding: Binding::Axis(code, direction),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Emits events for all buttons that changed since the last report.
+    fn report(&mut self, time: Duration, events: &mut Vec<ButtonEvent>) {
+        let buttons = self.map_buttons();
+        for (index, (&now, &before)) in buttons.iter().zip(&self.buttons).enumerate() {
+            if now != before
+                && let Some(button) = Button::from_usize(index)
+            {
+                events.push(ButtonEvent {
+                    button,
+                    pressed: now,
+                    time,
+                    player: self.slot,
+                });
+            }
+        }
+        self.buttons = buttons;
+    }
+
+    fn release_buttons(&mut self, events: &mut Vec<ButtonEvent>) {
+        let time = event_time();
+        for (index, pressed) in self.buttons.iter_mut().enumerate() {
+            if std::mem::take(pressed)
+                && let Some(button) = Button::from_usize(index)
+            {
+                events.push(ButtonEvent {
+                    button,
+                    pressed: false,
+                    time,
+                    player: self.slot,
+                });
+            }
+        }
+    }
+
+    fn map_buttons(&self) -> [bool; Button::COUNT] {
+        let mut state = [false; Button::COUNT];
+        for (button, binding) in self.map.iter() {
+            state[button.index()] |= self.is_active(binding);
+        }
+        state
+    }
+
+    fn is_active(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(code) => {
+                self.kind.has_keys() && code <= KEY_MAX && Self::has_bit(&self.keys, code)
+            }
+            // touch positions are not directions
+            Binding::Axis(_, _) if self.touch.is_some() => false,
+            Binding::Axis(code, direction) => {
+                let Some(Some(info)) = self.abs.get(code as usize) else {
+                    return false;
+                };
+                Self::axis_direction(info) == Some(direction)
+            }
+        }
+    }
+
+    pub(crate) fn stick(&self, stick: Stick) -> Option<Vec2> {
+        let (x, y) = stick.axes();
+        let x = self.abs[x as usize]?;
+        let y = self.abs[y as usize]?;
+        Some(vec2(x.normalized(), y.normalized()))
+    }
+
+    pub(crate) fn trigger(&self, trigger: Trigger) -> Option<f32> {
+        trigger
+            .axes()
+            .into_iter()
+            .find_map(|axis| self.abs[axis as usize])
+            .map(|info| info.normalized_positive())
+    }
+
+    /// Fingers on the screen, normalized to `0.0..=1.0`.
+    fn touches(&self) -> Vec<(i32, Vec2)> {
+        let Some(touch) = &self.touch else {
+            return Vec::new();
+        };
+
+        let normalized = |x: u16, y: u16, position: (i32, i32)| {
+            let (Some(mut x), Some(mut y)) = (self.abs[x as usize], self.abs[y as usize]) else {
+                return Vec2::ZERO;
+            };
+            x.value = position.0;
+            y.value = position.1;
+            vec2(x.normalized_positive(), y.normalized_positive())
+        };
+
+        if touch.multitouch {
+            touch
+                .slots
+                .iter()
+                .filter(|slot| slot.id >= 0)
+                .map(|slot| {
+                    let position = normalized(ABS_MT_POSITION_X, ABS_MT_POSITION_Y, slot.position);
+                    (slot.id, position)
+                })
+                .collect()
+        } else if Self::has_bit(&self.keys, BTN_TOUCH) {
+            let position = (
+                self.abs[ABS_X as usize].map_or(0, |info| info.value),
+                self.abs[ABS_Y as usize].map_or(0, |info| info.value),
+            );
+            vec![(0, normalized(ABS_X, ABS_Y, position))]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn mouse_pressed(&self) -> bool {
+        self.is_mouse && Self::has_bit(&self.keys, BTN_LEFT)
+    }
+
+    /// Which direction an axis is pushed past the threshold, if any.
+    fn axis_direction(info: &InputAbsInfo) -> Option<AxisDirection> {
+        let value = info.normalized();
+        if value <= -AXIS_THRESHOLD {
+            Some(AxisDirection::Negative)
+        } else if value >= AXIS_THRESHOLD {
+            Some(AxisDirection::Positive)
+        } else {
+            None
+        }
+    }
+
+    fn has_bit(bits: &[u8], bit: u16) -> bool {
+        let byte = bits[(bit / 8) as usize];
+        let mask = 1 << (bit % 8);
+        byte & mask != 0
+    }
+
+    fn set_bit(bits: &mut [u8], bit: u16, value: bool) {
+        let Some(byte) = bits.get_mut((bit / 8) as usize) else {
+            return;
+        };
+        let mask = 1 << (bit % 8);
+        if value {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+}
+
+/// Reads the state of an absolute axis, `EVIOCGABS`.
+fn read_abs_info(file: &File, axis: u16) -> Option<InputAbsInfo> {
+    let mut info = InputAbsInfo::default();
+    let request = request_code_read!(b'E', 0x40 + axis, size_of::<InputAbsInfo>());
+    let result = unsafe { nix::libc::ioctl(file.as_raw_fd(), request as _, &raw mut info) };
+    (result >= 0).then_some(info)
+}
+
+/// Fingers on a touchscreen, tracked through the multitouch protocol (type B),
+/// or `BTN_TOUCH` with `ABS_X`/`ABS_Y` for single touch screens.
+#[derive(Debug)]
+struct TouchState {
+    multitouch: bool,
+    slots: Vec<TouchSlot>,
+    current_slot: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TouchSlot {
+    // tracking id, -1 if no finger is down
+    id: i32,
+    position: (i32, i32),
+}
+
+impl TouchSlot {
+    const EMPTY: Self = Self {
+        id: -1,
+        position: (0, 0),
+    };
+}
+
+impl TouchState {
+    /// Touchscreens are direct input devices with `BTN_TOUCH`,
+    /// unlike touchpads, which move a cursor.
+    fn from_device(file: &File, supported_keys: &[u8]) -> Option<Self> {
+        let mut props = [0u8; 4];
+        unsafe { prop_bits(file.as_raw_fd(), &mut props) }.ok()?;
+        if !Device::has_bit(&props, INPUT_PROP_DIRECT)
+            || !Device::has_bit(supported_keys, BTN_TOUCH)
+        {
+            return None;
+        }
+
+        let slots = read_abs_info(file, ABS_MT_SLOT)
+            .filter(|_| read_abs_info(file, ABS_MT_POSITION_X).is_some())
+            .map(|info| (info.maximum.max(0) as usize + 1).min(MAX_TOUCHES));
+        if slots.is_none() && read_abs_info(file, ABS_X).is_none() {
+            return None;
+        }
+
+        Some(Self {
+            multitouch: slots.is_some(),
+            slots: vec![TouchSlot::EMPTY; slots.unwrap_or_default()],
+            current_slot: 0,
+        })
+    }
+
+    fn process(&mut self, code: u16, value: i32) {
+        if code == ABS_MT_SLOT {
+            self.current_slot = value.max(0) as usize;
+            return;
+        }
+
+        let Some(slot) = self.slots.get_mut(self.current_slot) else {
+            return;
+        };
+        match code {
+            ABS_MT_TRACKING_ID => slot.id = value,
+            ABS_MT_POSITION_X => slot.position.0 = value,
+            ABS_MT_POSITION_Y => slot.position.1 = value,
+            _ => {}
+        }
+    }
+
+    fn clear(&mut self) {
+        self.slots.fill(TouchSlot::EMPTY);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DeviceKind {
+    Keys,
+    Abs,
+    KeysAndAbs,
+}
+
+impl DeviceKind {
+    fn from_device(file: &File) -> Option<Self> {
+        let mut bits = [0u8; KEY_STATE_BYTES];
+        let has_keys = unsafe { key_state(file.as_raw_fd(), &mut bits) }.is_ok();
+
+        let has_abs = [ABS_X, ABS_Y, ABS_RX, ABS_RY, ABS_HAT0X, ABS_HAT0Y]
+            .into_iter()
+            .any(|axis| read_abs_info(file, axis).is_some());
+
+        match (has_keys, has_abs) {
+            (true, true) => Some(Self::KeysAndAbs),
+            (true, false) => Some(Self::Keys),
+            (false, true) => Some(Self::Abs),
+            (false, false) => None,
+        }
+    }
+
+    fn has_keys(self) -> bool {
+        matches!(self, Self::Keys | Self::KeysAndAbs)
+    }
+
+    fn has_abs(self) -> bool {
+        matches!(self, Self::Abs | Self::KeysAndAbs)
+    }
+}
+
+/// `struct input_event` as read from evdev devices.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, AnyBitPattern, NoUninit)]
+pub(crate) struct InputEvent {
+    pub(crate) seconds: c_long,
+    pub(crate) microseconds: c_long,
+    pub(crate) kind: u16,
+    pub(crate) code: u16,
+    pub(crate) value: i32,
+}
+
+impl InputEvent {
+    fn time(&self) -> Duration {
+        Duration::new(
+            self.seconds.max(0).cast_unsigned(),
+            (self.microseconds.max(0) * 1000) as u32,
+        )
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct InputId {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct InputAbsInfo {
+    value: i32,
+    minimum: i32,
+    maximum: i32,
+    fuzz: i32,
+    flat: i32,
+    resolution: i32,
+}
+
+impl InputAbsInfo {
+    /// Value mapped to `-1.0..=1.0`, with 0 at the center of the range.
+    fn normalized(&self) -> f32 {
+        let center = (self.minimum as f32 + self.maximum as f32) / 2.0;
+        let half_range = (self.maximum as f32 - self.minimum as f32) / 2.0;
+        if half_range <= 0.0 {
+            return 0.0;
+        }
+        ((self.value as f32 - center) / half_range).clamp(-1.0, 1.0)
+    }
+
+    /// Value mapped to `0.0..=1.0`, with 0 at the minimum, for triggers.
+    fn normalized_positive(&self) -> f32 {
+        let range = self.maximum as f32 - self.minimum as f32;
+        if range <= 0.0 {
+            return 0.0;
+        }
+        ((self.value as f32 - self.minimum as f32) / range).clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use strum::EnumCount as _;
+
+use crate::input::Button;
+
+const DEFAULT_REPEAT_DELAY: Duration = Duration::from_millis(400);
+const DEFAULT_REPEAT_INTERVAL: Duration = Duration::from_millis(80);
+const DEFAULT_LONG_PRESS: Duration = Duration::from_millis(500);
+
+/// Timing of auto-repeat and long presses.
+#[derive(Debug, Clone, Copy)]
+pub struct HoldSettings {
+    /// How long a button is held before it starts repeating.
+    pub repeat_delay: Duration,
+    /// Time between repeats.
+    pub repeat_interval: Duration,
+    /// How long a button is held to count as a long press.
+    pub long_press: Duration,
+}
+
+impl Default for HoldSettings {
+    fn default() -> Self {
+        Self {
+            repeat_delay: DEFAULT_REPEAT_DELAY,
+            repeat_interval: DEFAULT_REPEAT_INTERVAL,
+            long_press: DEFAULT_LONG_PRESS,
+        }
+    }
+}
+
+/// Tracks how long buttons are held, for repeats and long presses.
+///
+/// Times are on the input clock, which advances by the recorded frame times during replays.
+#[derive(Debug, Default)]
+pub(crate) struct HoldState {
+    pub(crate) settings: HoldSettings,
+    held_since: [Option<Duration>; Button::COUNT],
+    next_repeat: [Option<Duration>; Button::COUNT],
+    repeated: [bool; Button::COUNT],
+    long_pressed: [bool; Button::COUNT],
+    now: Option<Duration>,
+}
+
+impl HoldState {
+    pub(crate) fn update(
+        &mut self,
+        state: &[bool; Button::COUNT],
+        pressed: &[bool; Button::COUNT],
+        now: Duration,
+    ) {
+        let previous = self.now.replace(now);
+
+        for index in 0..Button::COUNT {
+            self.repeated[index] = false;
+            self.long_pressed[index] = false;
+
+            // a tap within a single frame still counts as a press
+            if pressed[index] {
+                self.repeated[index] = true;
+            }
+            if !state[index] {
+                self.held_since[index] = None;
+                self.next_repeat[index] = None;
+                continue;
+            }
+
+            let held_since = *self.held_since[index].get_or_insert(now);
+            let next_repeat =
+                self.next_repeat[index].get_or_insert(held_since + self.settings.repeat_delay);
+            if now >= *next_repeat {
+                self.repeated[index] = true;
+                // skip repeats that were missed during a long frame
+                while *next_repeat <= now {
+                    *next_repeat += self.settings.repeat_interval.max(Duration::from_millis(1));
+                }
+            }
+
+            let long_press = held_since + self.settings.long_press;
+            self.long_pressed[index] =
+                now >= long_press && previous.is_none_or(|previous| previous < long_press);
+        }
+    }
+
+    pub(crate) fn held_for(&self, button: Button) -> Duration {
+        match (self.held_since[button.index()], self.now) {
+            (Some(since), Some(now)) => now - since,
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn repeated(&self, button: Button) -> bool {
+        self.repeated[button.index()]
+    }
+
+    pub(crate) fn long_pressed(&self, button: Button) -> bool {
+        self.long_pressed[button.index()]
+    }
+}
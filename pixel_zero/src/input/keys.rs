@@ -1,4 +1,24 @@
+// event types
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+pub const EV_FF: u16 = 0x15;
+
+pub const SYN_REPORT: u16 = 0;
+pub const SYN_DROPPED: u16 = 3;
+
 pub const KEY_ESC: u16 = 1;
+pub const KEY_MAX: u16 = 0x2ff;
+
+// text input
+pub const KEY_BACKSPACE: u16 = 14;
+pub const KEY_ENTER: u16 = 28;
+pub const KEY_LEFTSHIFT: u16 = 42;
+pub const KEY_RIGHTSHIFT: u16 = 54;
+pub const KEY_SPACE: u16 = 57;
+pub const KEY_CAPSLOCK: u16 = 58;
+pub const KEY_KPENTER: u16 = 96;
 
 pub const KEY_A: u16 = 30;
 pub const KEY_B: u16 = 48;
@@ -22,12 +42,36 @@ pub const BTN_DPAD_RIGHT: u16 = 0x223;
 // left stick
 pub const ABS_X: u16 = 0x00;
 pub const ABS_Y: u16 = 0x01;
+// right stick
+pub const ABS_RX: u16 = 0x03;
+pub const ABS_RY: u16 = 0x04;
+// triggers, some devices report them as gas and brake instead
+pub const ABS_Z: u16 = 0x02;
+pub const ABS_RZ: u16 = 0x05;
+pub const ABS_GAS: u16 = 0x09;
+pub const ABS_BRAKE: u16 = 0x0a;
 // dpad
 pub const ABS_HAT0X: u16 = 0x10;
 pub const ABS_HAT0Y: u16 = 0x11;
+// multitouch
+pub const ABS_MT_SLOT: u16 = 0x2f;
+pub const ABS_MT_POSITION_X: u16 = 0x35;
+pub const ABS_MT_POSITION_Y: u16 = 0x36;
+pub const ABS_MT_TRACKING_ID: u16 = 0x39;
+pub const ABS_COUNT: usize = 0x40;
+
+// mouse
+pub const REL_X: u16 = 0x00;
+pub const REL_Y: u16 = 0x01;
+pub const BTN_LEFT: u16 = 0x110;
+pub const BTN_TOUCH: u16 = 0x14a;
 
-pub const THRESHOLD: i32 = 16000;
-pub const DEADZONE: i32 = 8000;
+// device properties
+pub const INPUT_PROP_DIRECT: u16 = 0x01;
+
+// buttons of joysticks and gamepads, other devices don't take a player slot
+pub const BTN_JOYSTICK: u16 = 0x120;
+pub const BTN_GAMEPAD_LAST: u16 = 0x13f;
 
 pub const BTN_SOUTH: u16 = 0x130;
 pub const BTN_EAST: u16 = 0x131;
@@ -35,3 +79,28 @@ pub const BTN_SELECT: u16 = 0x13A;
 pub const BTN_START: u16 = 0x13B;
 pub const BTN_TL: u16 = 0x136;
 pub const BTN_TR: u16 = 0x137;
+
+// extended buttons
+pub const BTN_NORTH: u16 = 0x133;
+pub const BTN_WEST: u16 = 0x134;
+pub const BTN_TL2: u16 = 0x138;
+pub const BTN_TR2: u16 = 0x139;
+pub const BTN_MODE: u16 = 0x13c;
+pub const BTN_THUMBL: u16 = 0x13d;
+pub const BTN_THUMBR: u16 = 0x13e;
+pub const KEY_X: u16 = 45;
+pub const KEY_Y: u16 = 21;
+pub const KEY_HOMEPAGE: u16 = 172;
+pub const KEY_MENU: u16 = 139;
+pub const KEY_VOLUMEUP: u16 = 115;
+pub const KEY_VOLUMEDOWN: u16 = 114;
+
+// system keys
+pub const KEY_MUTE: u16 = 113;
+pub const KEY_POWER: u16 = 116;
+pub const KEY_BRIGHTNESSDOWN: u16 = 224;
+pub const KEY_BRIGHTNESSUP: u16 = 225;
+
+// force feedback
+pub const FF_RUMBLE: u16 = 0x50;
+pub const FF_MAX: u16 = 0x7f;
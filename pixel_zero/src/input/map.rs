@@ -0,0 +1,379 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use strum::{EnumCount as _, IntoEnumIterator as _};
+use thiserror::Error;
+
+use crate::input::{Button, keys::*};
+
+#[derive(Debug, Error)]
+pub enum ButtonMapError {
+    #[error("I/O error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Line {0}: expected `[default]` or `[vendor:product]`")]
+    InvalidSection(usize),
+    #[error("Line {0}: expected `button = bindings`")]
+    InvalidLine(usize),
+    #[error("Line {0}: unknown button `{1}`")]
+    UnknownButton(usize, String),
+    #[error("Line {0}: invalid binding `{1}`")]
+    InvalidBinding(usize, String),
+}
+
+/// Direction of an absolute axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Negative,
+    Positive,
+}
+
+/// A physical input that can be bound to a `Button`.
+///
+/// Written as `key:CODE` or `abs:CODE+` / `abs:CODE-` in profile files,
+/// where codes are evdev codes in decimal or hex, e.g. `key:30`, `key:0x130` or `abs:0x11-`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// A key or button, like `KEY_A` (30) or `BTN_SOUTH` (0x130).
+    Key(u16),
+    /// One direction of an absolute axis, like `ABS_HAT0X` (0x10).
+    Axis(u16, AxisDirection),
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(code) => write!(f, "key:{code:#x}"),
+            Self::Axis(code, AxisDirection::Negative) => write!(f, "abs:{code:#x}-"),
+            Self::Axis(code, AxisDirection::Positive) => write!(f, "abs:{code:#x}+"),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, code) = s.split_once(':').ok_or(())?;
+        match kind {
+            "key" => {
+                let code = parse_code(code)?;
+                if code > KEY_MAX {
+                    return Err(());
+                }
+                Ok(Self::Key(code))
+            }
+            "abs" => {
+                let direction = match code.chars().last() {
+                    Some('-') => AxisDirection::Negative,
+                    Some('+') => AxisDirection::Positive,
+                    _ => return Err(()),
+                };
+                let code = parse_code(&code[..code.len() - 1])?;
+                if code as usize >= ABS_COUNT {
+                    return Err(());
+                }
+                Ok(Self::Axis(code, direction))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+fn parse_code(code: &str) -> Result<u16, ()> {
+    match code.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).map_err(|_| ()),
+        None => code.parse().map_err(|_| ()),
+    }
+}
+
+/// Maps physical inputs to `Button`s.
+/// The default map covers keyboards and common gamepads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ButtonMap {
+    bindings: [Vec<Binding>; Button::COUNT],
+}
+
+impl Default for ButtonMap {
+    fn default() -> Self {
+        let mut map = Self::empty();
+        let defaults = [
+            (Button::Up, KEY_UP, BTN_DPAD_UP),
+            (Button::Down, KEY_DOWN, BTN_DPAD_DOWN),
+            (Button::Left, KEY_LEFT, BTN_DPAD_LEFT),
+            (Button::Right, KEY_RIGHT, BTN_DPAD_RIGHT),
+            (Button::A, KEY_A, BTN_SOUTH),
+            (Button::B, KEY_B, BTN_EAST),
+            (Button::L, KEY_L, BTN_TL),
+            (Button::R, KEY_R, BTN_TR),
+            (Button::Start, KEY_DOT, BTN_START),
+            (Button::Select, KEY_COMMA, BTN_SELECT),
+        ];
+        for (button, key, pad) in defaults {
+            map.bind(button, Binding::Key(key));
+            map.bind(button, Binding::Key(pad));
+        }
+
+        let extended = [
+            (Button::X, &[KEY_X, BTN_NORTH][..]),
+            (Button::Y, &[KEY_Y, BTN_WEST]),
+            (Button::L2, &[BTN_TL2]),
+            (Button::R2, &[BTN_TR2]),
+            (Button::L3, &[BTN_THUMBL]),
+            (Button::R3, &[BTN_THUMBR]),
+            (Button::Home, &[KEY_HOMEPAGE, BTN_MODE]),
+            (Button::Menu, &[KEY_MENU]),
+            (Button::VolumeUp, &[KEY_VOLUMEUP]),
+            (Button::VolumeDown, &[KEY_VOLUMEDOWN]),
+        ];
+        for (button, codes) in extended {
+            for code in codes {
+                map.bind(button, Binding::Key(*code));
+            }
+        }
+
+        // left stick and dpad hat
+        for (x, y) in [(ABS_X, ABS_Y), (ABS_HAT0X, ABS_HAT0Y)] {
+            map.bind(Button::Left, Binding::Axis(x, AxisDirection::Negative));
+            map.bind(Button::Right, Binding::Axis(x, AxisDirection::Positive));
+            map.bind(Button::Up, Binding::Axis(y, AxisDirection::Negative));
+            map.bind(Button::Down, Binding::Axis(y, AxisDirection::Positive));
+        }
+        map
+    }
+}
+
+impl ButtonMap {
+    /// A map without any bindings.
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            bindings: std::array::from_fn(|_| Vec::new()),
+        }
+    }
+
+    /// Binds a physical input to a `Button`, removing it from any other button.
+    pub fn bind(&mut self, button: Button, binding: Binding) {
+        for bindings in &mut self.bindings {
+            bindings.retain(|b| *b != binding);
+        }
+        self.bindings[button.index()].push(binding);
+    }
+
+    /// Removes all bindings of a `Button`.
+    pub fn clear(&mut self, button: Button) {
+        self.bindings[button.index()].clear();
+    }
+
+    #[must_use]
+    pub fn bindings(&self, button: Button) -> &[Binding] {
+        &self.bindings[button.index()]
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Button, Binding)> + '_ {
+        Button::iter().flat_map(|button| {
+            self.bindings(button)
+                .iter()
+                .map(move |binding| (button, *binding))
+        })
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for button in Button::iter() {
+            write!(f, "{button} =")?;
+            for binding in self.bindings(button) {
+                write!(f, " {binding}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// USB vendor and product ID of an input device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceId {
+    pub vendor: u16,
+    pub product: u16,
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vendor, self.product)
+    }
+}
+
+impl FromStr for DeviceId {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (vendor, product) = s.split_once(':').ok_or(())?;
+        Ok(Self {
+            vendor: u16::from_str_radix(vendor, 16).map_err(|_| ())?,
+            product: u16::from_str_radix(product, 16).map_err(|_| ())?,
+        })
+    }
+}
+
+/// The default `ButtonMap`, plus maps for specific devices.
+///
+/// Profile files contain sections of `button = bindings` lines:
+///
+/// ```text
+/// # used by all devices without their own section
+/// [default]
+/// a = key:30 key:0x130
+/// up = key:103 abs:0x11-
+///
+/// # vendor:product in hex
+/// [045e:028e]
+/// a = key:0x130
+/// ```
+///
+/// A device section replaces the whole default map, buttons it does not list are unbound.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ButtonProfiles {
+    pub default: ButtonMap,
+    pub devices: HashMap<DeviceId, ButtonMap>,
+}
+
+impl ButtonProfiles {
+    /// Environment variable for the profile path, read by [`ButtonProfiles::path`].
+    pub const ENV_VAR: &str = "PIXEL_ZERO_CONTROLS";
+
+    /// The map used for a device.
+    #[must_use]
+    pub fn map_for(&self, id: DeviceId) -> &ButtonMap {
+        self.devices.get(&id).unwrap_or(&self.default)
+    }
+
+    /// Where profiles are loaded from and saved to.
+    /// `PIXEL_ZERO_CONTROLS` if set, otherwise `~/.config/pixel_zero/controls.conf`.
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os(Self::ENV_VAR) {
+            return Some(PathBuf::from(path));
+        }
+        let home = std::env::var_os("HOME")?;
+        Some(PathBuf::from(home).join(".config/pixel_zero/controls.conf"))
+    }
+
+    /// Loads the profiles from [`ButtonProfiles::path`].
+    /// Falls back to the defaults if the file is missing or malformed.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+
+        Self::load(&path).unwrap_or_else(|e| {
+            log::warn!("ignoring controls from {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or is malformed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ButtonMapError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Writes the profiles, creating parent directories as needed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ButtonMapError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ButtonProfiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[default]")?;
+        self.default.write(f)?;
+
+        let mut devices: Vec<_> = self.devices.iter().collect();
+        devices.sort_by_key(|(id, _)| **id);
+        for (id, map) in devices {
+            writeln!(f, "\n[{id}]")?;
+            map.write(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ButtonProfiles {
+    type Err = ButtonMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut profiles = Self::default();
+        // `None` before the first section, lines there belong to the default map
+        let mut section: Option<Option<DeviceId>> = None;
+        let mut map = ButtonMap::empty();
+
+        for (index, line) in s.lines().enumerate() {
+            let number = index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                profiles.insert(section, std::mem::replace(&mut map, ButtonMap::empty()));
+                section = match name.trim() {
+                    "default" => Some(None),
+                    id => Some(Some(
+                        id.parse()
+                            .map_err(|()| ButtonMapError::InvalidSection(number))?,
+                    )),
+                };
+                continue;
+            }
+
+            let (button, bindings) = line
+                .split_once('=')
+                .ok_or(ButtonMapError::InvalidLine(number))?;
+            let button = button.trim();
+            let button: Button = button
+                .parse()
+                .map_err(|_| ButtonMapError::UnknownButton(number, button.to_owned()))?;
+
+            for binding in bindings.split_whitespace() {
+                let binding = binding
+                    .parse()
+                    .map_err(|()| ButtonMapError::InvalidBinding(number, binding.to_owned()))?;
+                map.bind(button, binding);
+            }
+        }
+        profiles.insert(section, map);
+
+        Ok(profiles)
+    }
+}
+
+impl ButtonProfiles {
+    fn insert(&mut self, section: Option<Option<DeviceId>>, map: ButtonMap) {
+        match section {
+            Some(Some(id)) => {
+                self.devices.insert(id, map);
+            }
+            Some(None) => self.default = map,
+            // no lines before the first section keeps the built-in default
+            None if map == ButtonMap::empty() => {}
+            None => self.default = map,
+        }
+    }
+}
@@ -1,21 +1,46 @@
-use std::{
-    fs::File,
-    os::{
-        fd::AsRawFd,
-        unix::fs::{FileTypeExt, OpenOptionsExt},
+use std::time::{Duration, Instant, SystemTime};
+
+use glam::{UVec2, Vec2};
+use strum::{Display, EnumCount, EnumIter, EnumString};
+
+use crate::{
+    graphics::display::Rotation,
+    input::{
+        evdev::EvdevSource, hold::HoldState, keys::*, pointer::PointerState, source::push_changes,
     },
-    time::{Duration, Instant},
 };
 
-use nix::{ioctl_read, ioctl_read_buf};
-use strum::{EnumCount, EnumIter};
-
-use crate::input::keys::*;
+pub(crate) use crate::input::pointer::ScreenMapping;
+
+pub use crate::input::{
+    hold::HoldSettings,
+    map::{Binding, ButtonMap, ButtonProfiles, DeviceId},
+    player::{MAX_PLAYERS, PlayerInput},
+    pointer::Touch,
+    recording::Recording,
+    source::{InputSource, MemorySource, ScriptStep, ScriptedSource},
+    system::{SystemEvent, SystemHandlers},
+    text::TextInput,
+};
 
+mod evdev;
+mod hold;
 mod keys;
-
-/// Button layout similar to a Gameboy Advance.
-#[derive(Debug, Clone, Copy, EnumCount, EnumIter)]
+pub mod map;
+pub mod player;
+mod pointer;
+pub mod recording;
+mod rumble;
+pub mod source;
+mod system;
+mod text;
+
+/// Button layout similar to a Gameboy Advance, see [`Button::GBA`].
+///
+/// The buttons after `Select` are found on most other controllers,
+/// and only reported after [`Input::set_extended_buttons`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumCount, EnumIter, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
 pub enum Button {
     Up,
     Down,
@@ -27,6 +52,18 @@ pub enum Button {
     R,
     Start,
     Select,
+    X,
+    Y,
+    L2,
+    R2,
+    /// Left stick click.
+    L3,
+    /// Right stick click.
+    R3,
+    Home,
+    Menu,
+    VolumeUp,
+    VolumeDown,
 }
 
 impl Button {
@@ -50,98 +87,369 @@ impl Button {
             7 => Self::R,
             8 => Self::Start,
             9 => Self::Select,
+            10 => Self::X,
+            11 => Self::Y,
+            12 => Self::L2,
+            13 => Self::R2,
+            14 => Self::L3,
+            15 => Self::R3,
+            16 => Self::Home,
+            17 => Self::Menu,
+            18 => Self::VolumeUp,
+            19 => Self::VolumeDown,
             _ => return None,
         })
     }
 
+    /// Whether the button is beyond the GBA layout.
+    #[must_use]
+    pub fn is_extended(&self) -> bool {
+        self.index() >= Self::GBA.len()
+    }
+
     pub const BUTTON_COUNT: usize = Self::COUNT;
+
+    /// The buttons of a Gameboy Advance.
+    pub const GBA: [Self; 10] = [
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::A,
+        Self::B,
+        Self::L,
+        Self::R,
+        Self::Start,
+        Self::Select,
+    ];
+}
+
+const DEFAULT_STICK_DEADZONE: f32 = 0.15;
+const DEFAULT_TRIGGER_DEADZONE: f32 = 0.05;
+
+/// Analog sticks, values are in `-1.0..=1.0`, with positive y pointing down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumCount)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    fn axes(self) -> (u16, u16) {
+        match self {
+            Self::Left => (ABS_X, ABS_Y),
+            Self::Right => (ABS_RX, ABS_RY),
+        }
+    }
+}
+
+/// Analog triggers, values are in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumCount)]
+pub enum Trigger {
+    Left,
+    Right,
 }
 
-const SCAN_INTERVAL: Duration = Duration::from_secs(5);
-const KEY_STATE_BYTES: usize = 1024;
+impl Trigger {
+    fn axes(self) -> [u16; 2] {
+        match self {
+            Self::Left => [ABS_Z, ABS_BRAKE],
+            Self::Right => [ABS_RZ, ABS_GAS],
+        }
+    }
+}
+
+/// A press or release of a `Button`.
+#[derive(Debug, Clone, Copy)]
+pub struct ButtonEvent {
+    pub button: Button,
+    pub pressed: bool,
+    /// Kernel timestamp of the event.
+    pub time: Duration,
+    /// Player slot of the device, if it has one.
+    pub player: Option<usize>,
+}
+
+/// A physical input that was pressed, regardless of the button mapping.
+/// Used to set up mappings.
+#[derive(Debug, Clone, Copy)]
+pub struct RawPress {
+    pub device: DeviceId,
+    pub binding: Binding,
+}
+
+/// A device was plugged in or removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    Connected {
+        device: DeviceId,
+        /// Player slot, if the device is a controller.
+        player: Option<usize>,
+    },
+    Disconnected {
+        device: DeviceId,
+        player: Option<usize>,
+    },
+}
+
+/// Buttons and physical inputs that request quitting when held together, see [`Input::quit_requested`].
+#[derive(Debug, Clone)]
+pub struct QuitCombo {
+    pub buttons: Vec<Button>,
+    /// Inputs regardless of the button mapping, like the Escape key.
+    pub bindings: Vec<Binding>,
+    pub hold: Duration,
+}
+
+impl Default for QuitCombo {
+    /// Select and Start, held for a second.
+    fn default() -> Self {
+        Self {
+            buttons: vec![Button::Select, Button::Start],
+            bindings: Vec::new(),
+            hold: Duration::from_secs(1),
+        }
+    }
+}
+
+impl QuitCombo {
+    /// Pressing Escape on a keyboard.
+    #[must_use]
+    pub fn escape() -> Self {
+        Self {
+            buttons: Vec::new(),
+            bindings: vec![Binding::Key(KEY_ESC)],
+            hold: Duration::ZERO,
+        }
+    }
+
+    /// The default quit combos, [`QuitCombo::default`] and [`QuitCombo::escape`].
+    #[must_use]
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::default(), Self::escape()]
+    }
+}
 
 pub struct Input {
-    devices: Vec<Device>,
-    last_scanned: Instant,
+    // `None` when only using other sources
+    evdev: Option<EvdevSource>,
+    sources: Vec<Box<dyn InputSource>>,
+    // each combo with when it started being held, on the input clock
+    quit_combos: Vec<(QuitCombo, Option<Duration>)>,
+    quit_requested: bool,
+    recording: Option<Recording>,
+    // recording being replayed, and the next frame of it
+    replay: Option<(Recording, u32)>,
+    // advances by the time between updates, or the recorded frame times while replaying
+    clock: Duration,
+    last_update: Option<Instant>,
     current_state: [bool; Button::COUNT],
-    previous_state: [bool; Button::COUNT],
+    pressed: [bool; Button::COUNT],
+    released: [bool; Button::COUNT],
+    events: Vec<ButtonEvent>,
+    hold: HoldState,
+    sticks: [Vec2; Stick::COUNT],
+    triggers: [f32; Trigger::COUNT],
+    stick_deadzone: f32,
+    trigger_deadzone: f32,
+    extended_buttons: bool,
+    system_handlers: SystemHandlers,
+    // `None` follows the display of `Graphics`
+    screen: Option<ScreenMapping>,
+    pointer: PointerState,
 }
 
 impl Default for Input {
+    /// Uses the profiles from [`ButtonProfiles::from_env`].
     fn default() -> Self {
-        let devices = Self::scan_devices();
-        log::info!("found {} input devices", devices.len());
+        Self::with_profiles(ButtonProfiles::from_env())
+    }
+}
+
+impl Input {
+    /// Reads devices from `/dev/input`, mapped with `profiles`.
+    #[must_use]
+    pub fn with_profiles(profiles: ButtonProfiles) -> Self {
+        Self::new(Some(EvdevSource::new(profiles)), Vec::new())
+    }
+
+    /// Reads only from `source`, without touching `/dev/input`.
+    /// Used for tests and automation.
+    #[must_use]
+    pub fn with_source(source: impl InputSource + 'static) -> Self {
+        Self::new(None, vec![Box::new(source)])
+    }
+
+    fn new(evdev: Option<EvdevSource>, sources: Vec<Box<dyn InputSource>>) -> Self {
         Self {
-            devices,
-            last_scanned: Instant::now(),
+            evdev,
+            sources,
+            quit_combos: QuitCombo::defaults()
+                .into_iter()
+                .map(|combo| (combo, None))
+                .collect(),
+            quit_requested: false,
+            recording: None,
+            replay: None,
+            clock: Duration::ZERO,
+            last_update: None,
             current_state: [false; Button::COUNT],
-            previous_state: [false; Button::COUNT],
+            pressed: [false; Button::COUNT],
+            released: [false; Button::COUNT],
+            events: Vec::new(),
+            hold: HoldState::default(),
+            sticks: [Vec2::ZERO; Stick::COUNT],
+            triggers: [0.0; Trigger::COUNT],
+            stick_deadzone: DEFAULT_STICK_DEADZONE,
+            trigger_deadzone: DEFAULT_TRIGGER_DEADZONE,
+            extended_buttons: false,
+            system_handlers: SystemHandlers::default(),
+            screen: None,
+            pointer: PointerState::default(),
         }
     }
-}
 
-ioctl_read_buf!(key_state, b'E', 0x18, u8);
-ioctl_read!(abs_x, b'E', 0x40 + ABS_X, InputAbsInfo);
-ioctl_read!(abs_y, b'E', 0x40 + ABS_Y, InputAbsInfo);
-ioctl_read!(abs_hat0x, b'E', 0x40 + ABS_HAT0X, InputAbsInfo);
-ioctl_read!(abs_hat0y, b'E', 0x40 + ABS_HAT0Y, InputAbsInfo);
+    /// Adds another source, its buttons are combined with the others.
+    pub fn add_source(&mut self, source: impl InputSource + 'static) {
+        self.sources.push(Box::new(source));
+    }
 
-impl Input {
-    fn scan_devices() -> Vec<Device> {
-        let mut devices = Vec::new();
+    /// The button mapping of devices, `None` if `/dev/input` is not used.
+    #[must_use]
+    pub fn profiles(&self) -> Option<&ButtonProfiles> {
+        self.evdev.as_ref().map(EvdevSource::profiles)
+    }
 
-        for entry in std::fs::read_dir("/dev/input").unwrap() {
-            let Ok(entry) = entry else {
-                continue;
-            };
+    /// Replaces the button mapping, which applies to all devices immediately.
+    pub fn set_profiles(&mut self, profiles: ButtonProfiles) {
+        if let Some(evdev) = &mut self.evdev {
+            evdev.set_profiles(profiles);
+        }
+    }
 
-            let Ok(file_type) = entry.file_type() else {
-                continue;
-            };
+    /// Replaces the state read from devices, and the button events with its changes.
+    fn replace_state(
+        &mut self,
+        previous_state: [bool; Button::COUNT],
+        state: [bool; Button::COUNT],
+    ) {
+        self.events.clear();
+        push_changes(&mut self.events, &previous_state, &state);
+        self.current_state = state;
+    }
 
-            if !file_type.is_char_device() {
-                continue;
+    /// Updates the input state.
+    /// Should be called once per game loop iteration, usually at the start.
+    ///
+    /// All events since the last update are processed,
+    /// so a button pressed and released in between still counts as just pressed.
+    pub fn update(&mut self) {
+        let previous_state = self.current_state;
+        let now = Instant::now();
+        let mut frame_time = self
+            .last_update
+            .replace(now)
+            .map_or(Duration::ZERO, |last| now - last);
+
+        self.events.clear();
+        self.current_state = [false; Button::COUNT];
+        let sources = self
+            .evdev
+            .iter_mut()
+            .map(|evdev| evdev as &mut dyn InputSource)
+            .chain(self.sources.iter_mut().map(|source| &mut **source));
+        for source in sources {
+            let state = source.poll(&mut self.events);
+            for (current, pressed) in self.current_state.iter_mut().zip(state) {
+                *current |= pressed;
             }
+        }
 
-            let file_name = entry.file_name();
-            let Some(file_name) = file_name.to_str() else {
-                continue;
-            };
+        if let Some((recording, frame)) = &mut self.replay {
+            if *frame < recording.frames() {
+                let state = recording.state(*frame);
+                frame_time = recording.frame_time(*frame);
+                *frame += 1;
+                self.replace_state(previous_state, state);
+            } else {
+                log::info!("replay finished");
+                self.replay = None;
+            }
+        }
 
-            if !file_name.starts_with("event") {
-                continue;
+        if !self.extended_buttons {
+            self.events.retain(|event| !event.button.is_extended());
+            for pressed in &mut self.current_state[Button::GBA.len()..] {
+                *pressed = false;
             }
+        }
 
-            let Ok(file) = File::options()
-                .read(true)
-                .custom_flags(nix::libc::O_NONBLOCK)
-                .open(entry.path())
-            else {
-                continue;
-            };
+        if let Some(recording) = &mut self.recording {
+            recording.push(&self.current_state, frame_time);
+        }
 
-            let Some(kind) = DeviceKind::from_device(&file) else {
-                continue;
-            };
+        self.pressed = [false; Button::COUNT];
+        self.released = [false; Button::COUNT];
+        for event in &self.events {
+            if event.pressed {
+                self.pressed[event.button.index()] = true;
+            } else {
+                self.released[event.button.index()] = true;
+            }
+        }
 
-            devices.push(Device { file, kind });
+        self.clock += frame_time;
+        self.hold
+            .update(&self.current_state, &self.pressed, self.clock);
+
+        if let Some(evdev) = &mut self.evdev {
+            for event in evdev.system_events() {
+                self.system_handlers.handle(*event);
+            }
         }
-        devices
-    }
+        self.quit_requested = false;
+        for (combo, held_since) in &mut self.quit_combos {
+            let held = !(combo.buttons.is_empty() && combo.bindings.is_empty())
+                && combo
+                    .buttons
+                    .iter()
+                    .all(|button| self.current_state[button.index()])
+                && combo.bindings.iter().all(|binding| {
+                    self.evdev
+                        .as_ref()
+                        .is_some_and(|evdev| evdev.binding_active(*binding))
+                });
+            if !held {
+                *held_since = None;
+                continue;
+            }
 
-    /// Updates the input state.
-    /// Should be called once per game loop iteration, usually at the start.
-    pub fn update(&mut self) {
-        if self.last_scanned.elapsed() > SCAN_INTERVAL {
-            self.devices = Self::scan_devices();
-            self.last_scanned = Instant::now();
+            // requested once per hold, when it reaches the hold time
+            let first = held_since.is_none();
+            let since = *held_since.get_or_insert(self.clock);
+            let held_for = self.clock - since;
+            let held_before = (!first).then(|| held_for.saturating_sub(frame_time));
+            self.quit_requested |=
+                held_for >= combo.hold && held_before.is_none_or(|before| before < combo.hold);
         }
 
-        self.previous_state = self.current_state;
-        self.current_state = [false; Button::COUNT];
+        if let Some(evdev) = &mut self.evdev {
+            for (index, stick) in [Stick::Left, Stick::Right].into_iter().enumerate() {
+                self.sticks[index] = apply_radial_deadzone(evdev.stick(stick), self.stick_deadzone);
+            }
+            for (index, trigger) in [Trigger::Left, Trigger::Right].into_iter().enumerate() {
+                self.triggers[index] =
+                    apply_deadzone(evdev.trigger(trigger), self.trigger_deadzone);
+            }
 
-        for device in &self.devices {
-            device.poll(&mut self.current_state);
+            let mouse_motion = evdev.take_mouse_motion();
+            let screen = self.screen.unwrap_or_else(ScreenMapping::current);
+            self.pointer.update(
+                &screen,
+                evdev.touches(),
+                mouse_motion,
+                evdev.mouse_pressed(),
+            );
         }
     }
 
@@ -151,216 +459,268 @@ impl Input {
         self.current_state[button.index()]
     }
 
-    /// Whether a `Button` was just pressed.
+    /// Whether a `Button` was pressed since the last update.
     #[must_use]
     pub fn just_pressed(&self, button: Button) -> bool {
-        let current = self.current_state[button.index()];
-        let previous = self.previous_state[button.index()];
-        current && !previous
+        self.pressed[button.index()]
     }
 
-    /// Whether a `Button` was just released.
+    /// Whether a `Button` was released since the last update.
     #[must_use]
     pub fn just_released(&self, button: Button) -> bool {
-        let current = self.current_state[button.index()];
-        let previous = self.previous_state[button.index()];
-        !current && previous
+        self.released[button.index()]
     }
 
-    /// Returns the internal button state.
-    /// `Button::index()` returns the index for this array.
+    /// Whether a `Button` was just pressed, or is held long enough to repeat.
+    /// For scrolling through menus by holding a direction.
     #[must_use]
-    pub fn state(&self) -> &[bool; Button::COUNT] {
-        &self.current_state
+    pub fn just_pressed_repeat(&self, button: Button) -> bool {
+        self.hold.repeated(button)
     }
-}
 
-#[derive(Debug)]
-struct Device {
-    file: File,
-    kind: DeviceKind,
-}
+    /// How long a `Button` has been held, zero if it is released.
+    #[must_use]
+    pub fn held_for(&self, button: Button) -> Duration {
+        self.hold.held_for(button)
+    }
 
-impl Device {
-    fn poll(&self, state: &mut [bool; Button::COUNT]) {
-        if self.kind.has_keys() {
-            self.poll_keys(state);
-        }
+    /// Whether a `Button` has just been held for the long press duration.
+    /// Only true for a single update per press.
+    #[must_use]
+    pub fn long_pressed(&self, button: Button) -> bool {
+        self.hold.long_pressed(button)
+    }
 
-        if self.kind.has_abs() {
-            self.poll_abs(state);
-        }
+    /// Whether all `buttons` are held.
+    #[must_use]
+    pub fn is_chord_pressed(&self, buttons: &[Button]) -> bool {
+        !buttons.is_empty() && buttons.iter().all(|button| self.is_pressed(*button))
     }
 
-    fn poll_keys(&self, state: &mut [bool; Button::COUNT]) {
-        let mut bits = [0u8; KEY_STATE_BYTES];
-        if unsafe { key_state(self.file.as_raw_fd(), &mut bits) }.is_err() {
-            return;
-        }
+    /// Whether all `buttons` are held, and this update completed the chord.
+    /// Lets chords like L+R+Start trigger once, no matter the order they are pressed in.
+    #[must_use]
+    pub fn chord_just_pressed(&self, buttons: &[Button]) -> bool {
+        self.is_chord_pressed(buttons) && buttons.iter().any(|button| self.just_pressed(*button))
+    }
 
-        if Self::has_bit(&bits, KEY_ESC) {
-            std::process::exit(0);
-        }
+    #[must_use]
+    pub fn hold_settings(&self) -> HoldSettings {
+        self.hold.settings
+    }
 
-        state[Button::Up.index()] |=
-            Self::has_bit(&bits, KEY_UP) || Self::has_bit(&bits, BTN_DPAD_UP);
-        state[Button::Down.index()] |=
-            Self::has_bit(&bits, KEY_DOWN) || Self::has_bit(&bits, BTN_DPAD_DOWN);
-        state[Button::Left.index()] |=
-            Self::has_bit(&bits, KEY_LEFT) || Self::has_bit(&bits, BTN_DPAD_LEFT);
-        state[Button::Right.index()] |=
-            Self::has_bit(&bits, KEY_RIGHT) || Self::has_bit(&bits, BTN_DPAD_RIGHT);
-
-        state[Button::A.index()] |= Self::has_bit(&bits, KEY_A) || Self::has_bit(&bits, BTN_SOUTH);
-        state[Button::B.index()] |= Self::has_bit(&bits, KEY_B) || Self::has_bit(&bits, BTN_EAST);
-        state[Button::Start.index()] |=
-            Self::has_bit(&bits, KEY_DOT) || Self::has_bit(&bits, BTN_START);
-        state[Button::Select.index()] |=
-            Self::has_bit(&bits, KEY_COMMA) || Self::has_bit(&bits, BTN_SELECT);
-        state[Button::L.index()] |= Self::has_bit(&bits, KEY_L) || Self::has_bit(&bits, BTN_TL);
-        state[Button::R.index()] |= Self::has_bit(&bits, KEY_R) || Self::has_bit(&bits, BTN_TR);
-    }
-
-    fn poll_abs(&self, state: &mut [bool; Button::COUNT]) {
-        if let Some(value) = self.read_abs_x() {
-            AxisValue {
-                axis: Axis::X,
-                value,
-            }
-            .apply(state);
-        }
+    /// Sets the timing of repeats and long presses.
+    pub fn set_hold_settings(&mut self, settings: HoldSettings) {
+        self.hold.settings = settings;
+    }
 
-        if let Some(value) = self.read_abs_y() {
-            AxisValue {
-                axis: Axis::Y,
-                value,
-            }
-            .apply(state);
-        }
+    /// Presses and releases since the last update, in order.
+    #[must_use]
+    pub fn events(&self) -> &[ButtonEvent] {
+        &self.events
+    }
 
-        if let Some(value) = self.read_abs_hat0x() {
-            AxisValue {
-                axis: Axis::X,
-                value: value * THRESHOLD,
-            }
-            .apply(state);
+    /// Devices plugged in or removed since the last update, in order.
+    /// After the first update, this includes the devices that were already plugged in.
+    #[must_use]
+    pub fn connection_events(&self) -> &[ConnectionEvent] {
+        self.evdev
+            .as_ref()
+            .map_or(&[], EvdevSource::connection_events)
+    }
+
+    /// Physical inputs pressed since the last update, in order.
+    #[must_use]
+    pub fn raw_presses(&self) -> &[RawPress] {
+        self.evdev.as_ref().map_or(&[], EvdevSource::raw_presses)
+    }
+
+    /// Text typed on keyboards since the last update, in order.
+    /// The keys are still mapped to buttons as well.
+    #[must_use]
+    pub fn text_input(&self) -> &[TextInput] {
+        self.evdev.as_ref().map_or(&[], EvdevSource::text_input)
+    }
+
+    /// Power, volume and brightness keys pressed since the last update, in order.
+    /// Held volume and brightness keys repeat.
+    #[must_use]
+    pub fn system_events(&self) -> &[SystemEvent] {
+        self.evdev.as_ref().map_or(&[], EvdevSource::system_events)
+    }
+
+    /// Sets which system events are handled by changing the backlight or volume.
+    pub fn set_system_handlers(&mut self, handlers: SystemHandlers) {
+        self.system_handlers = handlers;
+    }
+
+    /// Position of an analog stick, zero if there is none.
+    #[must_use]
+    pub fn stick(&self, stick: Stick) -> Vec2 {
+        self.sticks[stick as usize]
+    }
+
+    /// How far an analog trigger is pulled, zero if there is none.
+    #[must_use]
+    pub fn trigger(&self, trigger: Trigger) -> f32 {
+        self.triggers[trigger as usize]
+    }
+
+    /// Sets the radial deadzone of the sticks, as a fraction of the full range.
+    /// Values outside of it are rescaled, so movement still starts at zero.
+    pub fn set_stick_deadzone(&mut self, deadzone: f32) {
+        self.stick_deadzone = deadzone.clamp(0.0, 0.99);
+    }
+
+    /// Sets the deadzone of the triggers, as a fraction of the full range.
+    pub fn set_trigger_deadzone(&mut self, deadzone: f32) {
+        self.trigger_deadzone = deadzone.clamp(0.0, 0.99);
+    }
+
+    /// Input of a single player, where `0` is P1.
+    /// Controllers get the first free slot when they connect.
+    #[must_use]
+    pub fn player(&self, player: usize) -> PlayerInput<'_> {
+        PlayerInput {
+            device: self.evdev.as_ref().and_then(|evdev| evdev.player(player)),
+            stick_deadzone: self.stick_deadzone,
+            trigger_deadzone: self.trigger_deadzone,
+            extended_buttons: self.extended_buttons,
         }
+    }
 
-        if let Some(value) = self.read_abs_hat0y() {
-            AxisValue {
-                axis: Axis::Y,
-                value: value * THRESHOLD,
-            }
-            .apply(state);
+    /// Rumbles the controller of a player, with `strength` in `0.0..=1.0`.
+    /// Replaces a running rumble, and a strength or duration of 0 stops it.
+    /// Does nothing if the controller has no force feedback.
+    pub fn rumble(&mut self, player: usize, strength: f32, duration: Duration) {
+        if let Some(evdev) = &mut self.evdev {
+            evdev.rumble(player, strength, duration);
         }
     }
 
-    fn read_abs_x(&self) -> Option<i32> {
-        let mut info = InputAbsInfo::default();
-        unsafe { abs_x(self.file.as_raw_fd(), &raw mut info) }
-            .ok()
-            .map(|_| info.value)
+    /// Reports buttons beyond the GBA layout, like X/Y and stick clicks.
+    /// Off by default, so their physical inputs can't trigger anything in GBA style games.
+    pub fn set_extended_buttons(&mut self, enabled: bool) {
+        self.extended_buttons = enabled;
     }
 
-    fn read_abs_y(&self) -> Option<i32> {
-        let mut info = InputAbsInfo::default();
-        unsafe { abs_y(self.file.as_raw_fd(), &raw mut info) }
-            .ok()
-            .map(|_| info.value)
+    #[must_use]
+    pub fn extended_buttons(&self) -> bool {
+        self.extended_buttons
     }
 
-    fn read_abs_hat0x(&self) -> Option<i32> {
-        let mut info = InputAbsInfo::default();
-        unsafe { abs_hat0x(self.file.as_raw_fd(), &raw mut info) }
-            .ok()
-            .map(|_| info.value)
+    /// Sets the size and rotation of the physical screen,
+    /// so touches and the mouse can be mapped to framebuffer coordinates.
+    ///
+    /// Only needed to override the display of the loaded [`Graphics`](crate::graphics::Graphics),
+    /// which is used by default. Without it, this is the framebuffer size without rotation.
+    pub fn set_display(&mut self, size: UVec2, rotation: Rotation) {
+        self.screen = Some(ScreenMapping { size, rotation });
     }
 
-    fn read_abs_hat0y(&self) -> Option<i32> {
-        let mut info = InputAbsInfo::default();
-        unsafe { abs_hat0y(self.file.as_raw_fd(), &raw mut info) }
-            .ok()
-            .map(|_| info.value)
+    /// Position of the first finger on a touchscreen, or else the mouse cursor,
+    /// in framebuffer coordinates.
+    /// Touches keep their last position after being lifted,
+    /// `None` until a pointer was used on the framebuffer.
+    #[must_use]
+    pub fn pointer_position(&self) -> Option<Vec2> {
+        self.pointer.position
     }
 
-    fn has_bit(bits: &[u8], bit: u16) -> bool {
-        let byte = bits[(bit / 8) as usize];
-        let mask = 1 << (bit % 8);
-        byte & mask != 0
+    /// Whether the screen is touched, or the left mouse button is held.
+    /// Touches inside the letterbox don't count.
+    #[must_use]
+    pub fn pointer_pressed(&self) -> bool {
+        self.pointer.pressed
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-enum DeviceKind {
-    Keys,
-    Abs,
-    KeysAndAbs,
-}
+    #[must_use]
+    pub fn pointer_just_pressed(&self) -> bool {
+        self.pointer.just_pressed
+    }
 
-impl DeviceKind {
-    fn from_device(file: &File) -> Option<Self> {
-        let mut bits = [0u8; KEY_STATE_BYTES];
-        let has_keys = unsafe { key_state(file.as_raw_fd(), &mut bits) }.is_ok();
-
-        let mut info = InputAbsInfo::default();
-        let has_abs = unsafe { abs_x(file.as_raw_fd(), &raw mut info) }.is_ok()
-            || unsafe { abs_y(file.as_raw_fd(), &raw mut info) }.is_ok()
-            || unsafe { abs_hat0x(file.as_raw_fd(), &raw mut info) }.is_ok()
-            || unsafe { abs_hat0y(file.as_raw_fd(), &raw mut info) }.is_ok();
-
-        match (has_keys, has_abs) {
-            (true, true) => Some(Self::KeysAndAbs),
-            (true, false) => Some(Self::Keys),
-            (false, true) => Some(Self::Abs),
-            (false, false) => None,
-        }
+    #[must_use]
+    pub fn pointer_just_released(&self) -> bool {
+        self.pointer.just_released
     }
 
-    fn has_keys(self) -> bool {
-        matches!(self, Self::Keys | Self::KeysAndAbs)
+    /// How far the pointer moved since it was pressed, `None` if it isn't pressed.
+    #[must_use]
+    pub fn pointer_drag(&self) -> Option<Vec2> {
+        Some(self.pointer.position? - self.pointer.press_position?)
     }
 
-    fn has_abs(self) -> bool {
-        matches!(self, Self::Abs | Self::KeysAndAbs)
+    /// All fingers on a touchscreen, in framebuffer coordinates.
+    #[must_use]
+    pub fn touches(&self) -> &[Touch] {
+        &self.pointer.touches
     }
-}
 
-#[repr(C)]
-#[derive(Debug, Default, Clone, Copy)]
-struct InputAbsInfo {
-    value: i32,
-    minimum: i32,
-    maximum: i32,
-    fuzz: i32,
-    flat: i32,
-    resolution: i32,
-}
+    /// Whether a quit combo was completed in the last update, like pressing Escape.
+    /// Only set for that update, so a game can ask "Quit game?" and carry on.
+    #[must_use]
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
 
-#[derive(Debug)]
-struct AxisValue {
-    axis: Axis,
-    value: i32,
-}
+    /// Replaces the quit combos, the default is [`QuitCombo::defaults`].
+    /// An empty list disables quit requests.
+    pub fn set_quit_combos(&mut self, combos: Vec<QuitCombo>) {
+        self.quit_combos = combos.into_iter().map(|combo| (combo, None)).collect();
+    }
+
+    /// Starts recording the button state of every update, replacing a running recording.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+
+    /// Stops recording, returns `None` if nothing was being recorded.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Plays back a recording one frame per update, in place of the button state from devices.
+    /// Hold times follow the recorded frame times, so repeats and long presses happen as recorded.
+    /// Devices take over again once it ends.
+    pub fn replay(&mut self, recording: Recording) {
+        self.replay = Some((recording, 0));
+    }
+
+    #[must_use]
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
 
-impl AxisValue {
-    fn apply(&self, button_state: &mut [bool; Button::COUNT]) {
-        let (negative, positive) = self.axis.buttons();
-        button_state[negative.index()] |= self.value <= -DEADZONE;
-        button_state[positive.index()] |= self.value >= DEADZONE;
+    /// Returns the internal button state.
+    /// `Button::index()` returns the index for this array.
+    #[must_use]
+    pub fn state(&self) -> &[bool; Button::COUNT] {
+        &self.current_state
     }
 }
 
-#[derive(Debug)]
-enum Axis {
-    X,
-    Y,
+/// Timestamp for events that don't come from the kernel, on the same clock.
+pub(crate) fn event_time() -> Duration {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
 }
 
-impl Axis {
-    fn buttons(&self) -> (Button, Button) {
-        match self {
-            Axis::X => (Button::Left, Button::Right),
-            Axis::Y => (Button::Up, Button::Down),
-        }
+/// Zeroes values inside the deadzone, and rescales the rest to start at 0.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value < deadzone {
+        0.0
+    } else {
+        (value - deadzone) / (1.0 - deadzone)
+    }
+}
+
+/// Like `apply_deadzone`, but on the length of the stick vector,
+/// so diagonals are not cut off like with a per-axis deadzone.
+fn apply_radial_deadzone(value: Vec2, deadzone: f32) -> Vec2 {
+    let length = value.length().min(1.0);
+    if length < deadzone {
+        return Vec2::ZERO;
     }
+    value.normalize_or_zero() * apply_deadzone(length, deadzone)
 }
@@ -0,0 +1,121 @@
+use glam::Vec2;
+
+use crate::input::{
+    Button, DeviceId, Stick, Trigger, apply_deadzone, apply_radial_deadzone, evdev::Device,
+};
+
+/// Number of player slots, P1 to P4.
+pub const MAX_PLAYERS: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    id: DeviceId,
+    connected: bool,
+}
+
+/// Assigns controllers to player slots.
+/// A slot stays reserved after its controller disconnects,
+/// so plugging the same controller back in returns it to the same player.
+#[derive(Debug, Default)]
+pub(crate) struct PlayerSlots {
+    slots: [Option<Slot>; MAX_PLAYERS],
+}
+
+impl PlayerSlots {
+    /// Picks a slot for a newly connected controller.
+    /// Prefers a disconnected slot of the same controller model, then a free slot,
+    /// then any disconnected slot. Returns `None` if all players are connected.
+    pub(crate) fn connect(&mut self, id: DeviceId) -> Option<usize> {
+        let index = self
+            .find(|slot| slot.is_some_and(|slot| !slot.connected && slot.id == id))
+            .or_else(|| self.find(|slot| slot.is_none()))
+            .or_else(|| self.find(|slot| slot.is_some_and(|slot| !slot.connected)))?;
+
+        self.slots[index] = Some(Slot {
+            id,
+            connected: true,
+        });
+        Some(index)
+    }
+
+    pub(crate) fn disconnect(&mut self, index: usize) {
+        if let Some(slot) = &mut self.slots[index] {
+            slot.connected = false;
+        }
+    }
+
+    fn find(&self, predicate: impl Fn(Option<Slot>) -> bool) -> Option<usize> {
+        self.slots.iter().position(|slot| predicate(*slot))
+    }
+}
+
+/// The input of a single player, from [`Input::player`](crate::input::Input::player).
+/// Everything reads as released while no controller is connected.
+pub struct PlayerInput<'a> {
+    pub(super) device: Option<&'a Device>,
+    pub(super) stick_deadzone: f32,
+    pub(super) trigger_deadzone: f32,
+    pub(super) extended_buttons: bool,
+}
+
+impl PlayerInput<'_> {
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.device.is_some()
+    }
+
+    /// Vendor and product of the controller.
+    #[must_use]
+    pub fn device_id(&self) -> Option<DeviceId> {
+        self.device.map(|device| device.id)
+    }
+
+    /// Whether a `Button` is pressed.
+    #[must_use]
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.reports(button)
+            && self
+                .device
+                .is_some_and(|device| device.buttons[button.index()])
+    }
+
+    /// Whether a `Button` was pressed since the last update.
+    #[must_use]
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.reports(button)
+            && self
+                .device
+                .is_some_and(|device| device.pressed[button.index()])
+    }
+
+    /// Whether a `Button` was released since the last update.
+    #[must_use]
+    pub fn just_released(&self, button: Button) -> bool {
+        self.reports(button)
+            && self
+                .device
+                .is_some_and(|device| device.released[button.index()])
+    }
+
+    fn reports(&self, button: Button) -> bool {
+        self.extended_buttons || !button.is_extended()
+    }
+
+    /// Position of an analog stick, zero if there is none.
+    #[must_use]
+    pub fn stick(&self, stick: Stick) -> Vec2 {
+        self.device
+            .and_then(|device| device.stick(stick))
+            .map_or(Vec2::ZERO, |value| {
+                apply_radial_deadzone(value, self.stick_deadzone)
+            })
+    }
+
+    /// How far an analog trigger is pulled, zero if there is none.
+    #[must_use]
+    pub fn trigger(&self, trigger: Trigger) -> f32 {
+        self.device
+            .and_then(|device| device.trigger(trigger))
+            .map_or(0.0, |value| apply_deadzone(value, self.trigger_deadzone))
+    }
+}
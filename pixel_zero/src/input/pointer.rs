@@ -0,0 +1,149 @@
+use glam::{UVec2, Vec2, uvec2, vec2};
+use parking_lot::Mutex;
+
+use crate::{HEIGHT, WIDTH, graphics::display::Rotation};
+
+/// A finger on a touchscreen, in framebuffer coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    /// Stays the same while the finger is down.
+    pub id: i32,
+    pub position: Vec2,
+}
+
+// the display of the loaded `Graphics`, so input follows it without being told
+static CURRENT: Mutex<Option<ScreenMapping>> = Mutex::new(None);
+
+/// Maps screen coordinates into the framebuffer, like the screen pass does in reverse.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScreenMapping {
+    pub(crate) size: UVec2,
+    pub(crate) rotation: Rotation,
+}
+
+impl Default for ScreenMapping {
+    fn default() -> Self {
+        Self {
+            size: uvec2(WIDTH, HEIGHT),
+            rotation: Rotation::Deg0,
+        }
+    }
+}
+
+impl ScreenMapping {
+    /// Set by `Graphics` while it is loaded.
+    pub(crate) fn set_current(mapping: Option<Self>) {
+        *CURRENT.lock() = mapping;
+    }
+
+    /// The display of the loaded `Graphics`, or the framebuffer size without it.
+    pub(crate) fn current() -> Self {
+        CURRENT.lock().unwrap_or_default()
+    }
+
+    fn logical_size() -> Vec2 {
+        vec2(WIDTH as f32, HEIGHT as f32)
+    }
+
+    /// Size of the screen as seen after rotating.
+    fn rotated_size(&self) -> Vec2 {
+        let size = self.size.as_vec2();
+        if self.rotation.is_portrait() {
+            vec2(size.y, size.x)
+        } else {
+            size
+        }
+    }
+
+    /// Maps a position on the panel, from 0 to 1 with y pointing down,
+    /// to framebuffer coordinates. Returns `None` inside the letterbox.
+    pub(crate) fn map(&self, position: Vec2) -> Option<Vec2> {
+        // same steps as `screen.vert` and `screen.frag`, texcoords have y pointing up
+        let uv = vec2(position.x, 1.0 - position.y);
+        let mut uv = match self.rotation {
+            Rotation::Deg0 => uv,
+            Rotation::Deg90 => vec2(1.0 - uv.y, uv.x),
+            Rotation::Deg180 => vec2(1.0 - uv.x, 1.0 - uv.y),
+            Rotation::Deg270 => vec2(uv.y, 1.0 - uv.x),
+        };
+
+        let logical = Self::logical_size();
+        let screen = self.rotated_size();
+        let target_aspect = logical.x / logical.y;
+        let screen_aspect = screen.x / screen.y;
+        if screen_aspect > target_aspect {
+            let scale = target_aspect / screen_aspect;
+            uv.x = (uv.x - 0.5) / scale + 0.5;
+        } else {
+            let scale = screen_aspect / target_aspect;
+            uv.y = (uv.y - 0.5) / scale + 0.5;
+        }
+
+        if !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+            return None;
+        }
+        Some(vec2(uv.x, 1.0 - uv.y) * logical)
+    }
+
+    /// Screen pixels per framebuffer pixel.
+    pub(crate) fn scale(&self) -> f32 {
+        let scale = self.rotated_size() / Self::logical_size();
+        scale.x.min(scale.y).max(f32::EPSILON)
+    }
+}
+
+/// The primary touch, or the mouse if nothing is touched.
+#[derive(Debug, Default)]
+pub(crate) struct PointerState {
+    pub(crate) position: Option<Vec2>,
+    pub(crate) pressed: bool,
+    pub(crate) just_pressed: bool,
+    pub(crate) just_released: bool,
+    pub(crate) press_position: Option<Vec2>,
+    // kept separately, so touching doesn't move the mouse
+    mouse_position: Option<Vec2>,
+    pub(crate) touches: Vec<Touch>,
+}
+
+impl PointerState {
+    pub(crate) fn update(
+        &mut self,
+        mapping: &ScreenMapping,
+        touches: impl Iterator<Item = (i32, Vec2)>,
+        mouse_motion: Option<Vec2>,
+        mouse_pressed: bool,
+    ) {
+        self.touches.clear();
+        self.touches.extend(touches.filter_map(|(id, position)| {
+            Some(Touch {
+                id,
+                position: mapping.map(position)?,
+            })
+        }));
+
+        if let Some(motion) = mouse_motion {
+            let logical = ScreenMapping::logical_size();
+            let position = self.mouse_position.unwrap_or(logical / 2.0) + motion / mapping.scale();
+            self.mouse_position = Some(position.clamp(Vec2::ZERO, logical - 1.0));
+        }
+
+        let was_pressed = self.pressed;
+        if let Some(touch) = self.touches.first() {
+            self.position = Some(touch.position);
+            self.pressed = true;
+        } else {
+            if self.mouse_position.is_some() {
+                self.position = self.mouse_position;
+            }
+            self.pressed = mouse_pressed && self.mouse_position.is_some();
+        }
+
+        self.just_pressed = self.pressed && !was_pressed;
+        self.just_released = !self.pressed && was_pressed;
+        if self.just_pressed {
+            self.press_position = self.position;
+        } else if !self.pressed {
+            self.press_position = None;
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use strum::EnumCount as _;
+use thiserror::Error;
+
+use crate::{input::Button, io::ReadBytes as _};
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("I/O error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Invalid recording magic: {0:?}")]
+    InvalidMagic(Vec<u8>),
+    #[error("Recording version should be 0, but is {0}")]
+    InvalidVersion(u32),
+    #[error("Recording data should be {expected} bytes, but is {found}")]
+    InvalidLength { expected: u64, found: u64 },
+}
+
+/// `Button` states of consecutive frames, recorded with [`Input::start_recording`](crate::input::Input::start_recording).
+///
+/// Only frames where the state changed are stored,
+/// as the frame number and a bitmask of the pressed buttons.
+/// The duration of every frame is stored too, so repeats and long presses play back the same.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    changes: Vec<(u32, u32)>,
+    // in microseconds
+    frame_times: Vec<u32>,
+}
+
+impl Recording {
+    const MAGIC: &[u8; 8] = b"pzinputs";
+    const VERSION: u32 = 0;
+
+    /// Number of recorded frames.
+    #[must_use]
+    pub fn frames(&self) -> u32 {
+        self.frame_times.len() as u32
+    }
+
+    /// Time since the frame before, zero past the end.
+    #[must_use]
+    pub fn frame_time(&self, frame: u32) -> Duration {
+        self.frame_times
+            .get(frame as usize)
+            .map_or(Duration::ZERO, |micros| {
+                Duration::from_micros(u64::from(*micros))
+            })
+    }
+
+    /// The button state of a frame, all released past the end.
+    #[must_use]
+    pub fn state(&self, frame: u32) -> [bool; Button::COUNT] {
+        if frame >= self.frames() {
+            return [false; Button::COUNT];
+        }
+
+        let index = self.changes.partition_point(|(start, _)| *start <= frame);
+        let bits = index
+            .checked_sub(1)
+            .map_or(0, |index| self.changes[index].1);
+        std::array::from_fn(|button| bits & (1 << button) != 0)
+    }
+
+    /// Appends the state of the next frame, and the time since the frame before.
+    pub fn push(&mut self, state: &[bool; Button::COUNT], frame_time: Duration) {
+        let bits = state
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed)| **pressed)
+            .fold(0, |bits, (button, _)| bits | 1 << button);
+
+        let changed = self
+            .changes
+            .last()
+            .map_or(bits != 0, |(_, last)| *last != bits);
+        if changed {
+            self.changes.push((self.frames(), bits));
+        }
+        self.frame_times
+            .push(frame_time.as_micros().min(u128::from(u32::MAX)) as u32);
+    }
+
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read, or is not a valid recording.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::load_read(&mut reader)
+    }
+
+    /// # Errors
+    ///
+    /// Fails if the data cannot be read, or is not a valid recording.
+    pub fn load_read(reader: &mut impl Read) -> Result<Self, RecordingError> {
+        let magic = reader.read_bytes(Self::MAGIC.len())?;
+        if magic != Self::MAGIC {
+            return Err(RecordingError::InvalidMagic(magic));
+        }
+
+        let version = reader.read_u32()?;
+        if version != Self::VERSION {
+            return Err(RecordingError::InvalidVersion(version));
+        }
+
+        let frames = reader.read_u32()?;
+        let count = reader.read_u32()?;
+
+        // check the counts before allocating for them
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let expected = u64::from(count) * 8 + u64::from(frames) * 4;
+        if data.len() as u64 != expected {
+            return Err(RecordingError::InvalidLength {
+                expected,
+                found: data.len() as u64,
+            });
+        }
+
+        let mut data = data.as_slice();
+        let mut changes = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let frame = data.read_u32()?;
+            let bits = data.read_u32()?;
+            changes.push((frame, bits));
+        }
+        let frame_times = (0..frames)
+            .map(|_| data.read_u32())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            changes,
+            frame_times,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Fails if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Fails if the data cannot be written.
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), RecordingError> {
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&Self::VERSION.to_le_bytes())?;
+        writer.write_all(&self.frames().to_le_bytes())?;
+        writer.write_all(&(self.changes.len() as u32).to_le_bytes())?;
+        for (frame, bits) in &self.changes {
+            writer.write_all(&frame.to_le_bytes())?;
+            writer.write_all(&bits.to_le_bytes())?;
+        }
+        for micros in &self.frame_times {
+            writer.write_all(&micros.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::{fs::File, io::Write as _, os::fd::AsRawFd as _, time::Duration};
+
+use bytemuck::bytes_of;
+use nix::{ioctl_read_buf, request_code_write};
+
+use crate::input::{evdev::InputEvent, keys::*};
+
+const FF_BITS_BYTES: usize = FF_MAX as usize / 8 + 1;
+
+ioctl_read_buf!(ff_bits, b'E', 0x20 + EV_FF, u8);
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct FfTrigger {
+    button: u16,
+    interval: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct FfReplay {
+    length: u16,
+    delay: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct FfRumbleEffect {
+    strong_magnitude: u16,
+    weak_magnitude: u16,
+}
+
+// the largest member of the kernel union, only here for its size and alignment
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FfPeriodicEffect {
+    waveform: u16,
+    period: u16,
+    magnitude: i16,
+    offset: i16,
+    phase: u16,
+    envelope: [u16; 4],
+    custom_len: u32,
+    custom_data: *mut i16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union FfEffectData {
+    rumble: FfRumbleEffect,
+    periodic: FfPeriodicEffect,
+}
+
+/// `struct ff_effect`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FfEffect {
+    kind: u16,
+    id: i16,
+    direction: u16,
+    trigger: FfTrigger,
+    replay: FfReplay,
+    data: FfEffectData,
+}
+
+/// A force feedback rumble effect uploaded to a device.
+/// The kernel frees it when the device is closed.
+#[derive(Debug)]
+pub(crate) struct Rumble {
+    // assigned by the kernel on the first upload, then updated in place
+    effect: Option<i16>,
+}
+
+impl Rumble {
+    /// Returns `None` if the device has no rumble motors.
+    pub(crate) fn new(file: &File) -> Option<Self> {
+        let mut bits = [0u8; FF_BITS_BYTES];
+        unsafe { ff_bits(file.as_raw_fd(), &mut bits) }.ok()?;
+        let supported = bits[FF_RUMBLE as usize / 8] & (1 << (FF_RUMBLE % 8)) != 0;
+        supported.then_some(Self { effect: None })
+    }
+
+    /// Rumbles with `strength` in `0.0..=1.0`, replacing the current effect.
+    /// A strength or duration of 0 stops rumbling.
+    pub(crate) fn play(
+        &mut self,
+        file: &File,
+        strength: f32,
+        duration: Duration,
+    ) -> std::io::Result<()> {
+        // the kernel plays an effect with a length of 0 forever
+        if strength <= 0.0 || duration.is_zero() {
+            return match self.effect {
+                Some(id) => Self::write_event(file, id, 0),
+                None => Ok(()),
+            };
+        }
+
+        let magnitude = (strength.min(1.0) * f32::from(u16::MAX)) as u16;
+        let mut effect = FfEffect {
+            kind: FF_RUMBLE,
+            id: self.effect.unwrap_or(-1),
+            direction: 0,
+            trigger: FfTrigger::default(),
+            replay: FfReplay {
+                length: duration.as_millis().clamp(1, u128::from(u16::MAX)) as u16,
+                delay: 0,
+            },
+            data: FfEffectData {
+                rumble: FfRumbleEffect {
+                    strong_magnitude: magnitude,
+                    weak_magnitude: magnitude,
+                },
+            },
+        };
+
+        // `EVIOCSFF` writes the assigned id back into the effect
+        let request = request_code_write!(b'E', 0x80, size_of::<FfEffect>());
+        let result = unsafe { nix::libc::ioctl(file.as_raw_fd(), request as _, &raw mut effect) };
+        if result < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        self.effect = Some(effect.id);
+
+        Self::write_event(file, effect.id, 1)
+    }
+
+    fn write_event(mut file: &File, id: i16, value: i32) -> std::io::Result<()> {
+        let event = InputEvent {
+            kind: EV_FF,
+            code: id.cast_unsigned(),
+            value,
+            ..InputEvent::default()
+        };
+        file.write_all(bytes_of(&event))
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use parking_lot::Mutex;
+use strum::{EnumCount as _, IntoEnumIterator as _};
+
+use crate::input::{Button, ButtonEvent, event_time};
+
+/// Provides button state to [`Input`](crate::input::Input).
+pub trait InputSource {
+    /// Reads input since the last call, once per `Input::update`.
+    /// Appends presses and releases to `events` in order, and returns the state after them.
+    fn poll(&mut self, events: &mut Vec<ButtonEvent>) -> [bool; Button::COUNT];
+}
+
+/// Buttons held for a number of frames, see [`ScriptedSource`].
+#[derive(Debug, Clone)]
+pub struct ScriptStep {
+    pub buttons: Vec<Button>,
+    pub frames: u32,
+}
+
+/// Plays back a fixed list of steps, one frame per poll.
+/// Everything is released once it is finished.
+#[derive(Debug, Clone)]
+pub struct ScriptedSource {
+    steps: VecDeque<ScriptStep>,
+    state: [bool; Button::COUNT],
+}
+
+impl ScriptedSource {
+    #[must_use]
+    pub fn new(steps: Vec<ScriptStep>) -> Self {
+        Self {
+            steps: steps.into(),
+            state: [false; Button::COUNT],
+        }
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl InputSource for ScriptedSource {
+    fn poll(&mut self, events: &mut Vec<ButtonEvent>) -> [bool; Button::COUNT] {
+        while self.steps.front().is_some_and(|step| step.frames == 0) {
+            self.steps.pop_front();
+        }
+
+        let mut state = [false; Button::COUNT];
+        if let Some(step) = self.steps.front_mut() {
+            for button in &step.buttons {
+                state[button.index()] = true;
+            }
+            step.frames -= 1;
+        }
+
+        push_changes(events, &self.state, &state);
+        self.state = state;
+        state
+    }
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    state: [bool; Button::COUNT],
+    events: Vec<(Button, bool)>,
+}
+
+/// Buttons set from code. Clones share their state,
+/// so one clone can be given to `Input` while another presses buttons.
+///
+/// ```
+/// use pixel_zero::input::{Button, Input, MemorySource};
+///
+/// let source = MemorySource::default();
+/// let mut input = Input::with_source(source.clone());
+///
+/// source.tap(Button::A);
+/// input.update();
+/// assert!(input.just_pressed(Button::A));
+/// assert!(!input.is_pressed(Button::A));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemorySource(Arc<Mutex<MemoryState>>);
+
+impl MemorySource {
+    pub fn press(&self, button: Button) {
+        self.set(button, true);
+    }
+
+    pub fn release(&self, button: Button) {
+        self.set(button, false);
+    }
+
+    /// Presses and releases a button, both seen by the next update.
+    pub fn tap(&self, button: Button) {
+        self.press(button);
+        self.release(button);
+    }
+
+    pub fn set(&self, button: Button, pressed: bool) {
+        let mut inner = self.0.lock();
+        if inner.state[button.index()] != pressed {
+            inner.state[button.index()] = pressed;
+            inner.events.push((button, pressed));
+        }
+    }
+}
+
+impl InputSource for MemorySource {
+    fn poll(&mut self, events: &mut Vec<ButtonEvent>) -> [bool; Button::COUNT] {
+        let mut inner = self.0.lock();
+        let time = event_time();
+        events.extend(inner.events.drain(..).map(|(button, pressed)| ButtonEvent {
+            button,
+            pressed,
+            time,
+            player: None,
+        }));
+        inner.state
+    }
+}
+
+/// Appends events for all buttons that differ between two states.
+pub(crate) fn push_changes(
+    events: &mut Vec<ButtonEvent>,
+    previous: &[bool; Button::COUNT],
+    state: &[bool; Button::COUNT],
+) {
+    let time = event_time();
+    for button in Button::iter() {
+        let pressed = state[button.index()];
+        if pressed != previous[button.index()] {
+            events.push(ButtonEvent {
+                button,
+                pressed,
+                time,
+                player: None,
+            });
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    input::keys::*,
+    system::{Backlight, Volume},
+};
+
+const BRIGHTNESS_STEP: f32 = 0.1;
+const VOLUME_STEP: i32 = 5;
+
+/// Hardware keys meant for the system rather than the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemEvent {
+    Power,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    BrightnessUp,
+    BrightnessDown,
+}
+
+impl SystemEvent {
+    /// The event of a key press, `repeat` for keys that are held.
+    pub(crate) fn from_key(code: u16, repeat: bool) -> Option<Self> {
+        Some(match code {
+            KEY_POWER if !repeat => Self::Power,
+            KEY_MUTE if !repeat => Self::Mute,
+            KEY_VOLUMEUP => Self::VolumeUp,
+            KEY_VOLUMEDOWN => Self::VolumeDown,
+            KEY_BRIGHTNESSUP => Self::BrightnessUp,
+            KEY_BRIGHTNESSDOWN => Self::BrightnessDown,
+            _ => return None,
+        })
+    }
+}
+
+/// Built-in handling of `SystemEvent`s, set with [`Input::set_system_handlers`](crate::input::Input::set_system_handlers).
+/// All are off by default, events are reported either way.
+#[derive(Debug, Clone, Default)]
+pub struct SystemHandlers {
+    /// Changes the brightness of this backlight with the brightness keys.
+    pub backlight: Option<Backlight>,
+    /// Changes this volume with the volume and mute keys.
+    pub volume: Option<Volume>,
+}
+
+impl SystemHandlers {
+    /// Handles everything, with the first backlight and the `Master` volume.
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            backlight: Backlight::find(),
+            volume: Some(Volume::default()),
+        }
+    }
+
+    pub(crate) fn handle(&self, event: SystemEvent) {
+        let result = match (event, &self.backlight, &self.volume) {
+            (SystemEvent::BrightnessUp, Some(backlight), _) => {
+                backlight.change_brightness(BRIGHTNESS_STEP)
+            }
+            (SystemEvent::BrightnessDown, Some(backlight), _) => {
+                backlight.change_brightness(-BRIGHTNESS_STEP)
+            }
+            // `amixer` takes too long to wait for in the game loop
+            (SystemEvent::VolumeUp, _, Some(volume)) => {
+                volume.queue_change(VOLUME_STEP);
+                Ok(())
+            }
+            (SystemEvent::VolumeDown, _, Some(volume)) => {
+                volume.queue_change(-VOLUME_STEP);
+                Ok(())
+            }
+            (SystemEvent::Mute, _, Some(volume)) => {
+                volume.queue_toggle_mute();
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            log::warn!("failed to handle {event:?}: {e}");
+        }
+    }
+}
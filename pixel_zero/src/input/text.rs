@@ -0,0 +1,71 @@
+use crate::input::keys::*;
+
+/// Text typed on a physical keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextInput {
+    Char(char),
+    Backspace,
+    Enter,
+}
+
+/// Translates a key press with a US layout.
+pub(crate) fn translate(code: u16, shift: bool, caps_lock: bool) -> Option<TextInput> {
+    match code {
+        KEY_BACKSPACE => return Some(TextInput::Backspace),
+        KEY_ENTER | KEY_KPENTER => return Some(TextInput::Enter),
+        KEY_SPACE => return Some(TextInput::Char(' ')),
+        _ => {}
+    }
+
+    let (normal, shifted) = us_layout(code)?;
+    // caps lock only affects letters
+    let shift = if normal.is_ascii_alphabetic() {
+        shift != caps_lock
+    } else {
+        shift
+    };
+    Some(TextInput::Char(if shift { shifted } else { normal }))
+}
+
+/// Characters of a key, without and with shift.
+fn us_layout(code: u16) -> Option<(char, char)> {
+    const NUMBER_ROW: &[(char, char)] = &[
+        ('1', '!'),
+        ('2', '@'),
+        ('3', '#'),
+        ('4', '$'),
+        ('5', '%'),
+        ('6', '^'),
+        ('7', '&'),
+        ('8', '*'),
+        ('9', '('),
+        ('0', ')'),
+        ('-', '_'),
+        ('=', '+'),
+    ];
+
+    let letter = |c: char| Some((c, c.to_ascii_uppercase()));
+    match code {
+        2..=13 => NUMBER_ROW.get(code as usize - 2).copied(),
+        16..=25 => letter(b"qwertyuiop"[code as usize - 16] as char),
+        26 => Some(('[', '{')),
+        27 => Some((']', '}')),
+        30..=38 => letter(b"asdfghjkl"[code as usize - 30] as char),
+        39 => Some((';', ':')),
+        40 => Some(('\'', '"')),
+        41 => Some(('`', '~')),
+        43 => Some(('\\', '|')),
+        44..=50 => letter(b"zxcvbnm"[code as usize - 44] as char),
+        51 => Some((',', '<')),
+        52 => Some(('.', '>')),
+        53 => Some(('/', '?')),
+        // keypad, without num lock handling
+        55 => Some(('*', '*')),
+        71..=83 => {
+            let c = b"789-456+1230."[code as usize - 71] as char;
+            Some((c, c))
+        }
+        98 => Some(('/', '/')),
+        _ => None,
+    }
+}
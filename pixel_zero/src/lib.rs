@@ -8,8 +8,10 @@ pub mod input;
 pub mod io;
 pub mod log;
 pub mod meta;
+pub mod system;
 mod terminal;
 pub mod ui;
+mod vt;
 
 pub use glam;
 
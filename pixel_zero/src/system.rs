@@ -0,0 +1,206 @@
+//! Control of the device itself, like the display backlight and audio volume.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        Arc, OnceLock,
+        mpsc::{self, Sender},
+    },
+};
+
+use thiserror::Error;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+// never turn the screen off completely
+const MIN_BRIGHTNESS: f32 = 0.05;
+
+#[derive(Debug, Error)]
+pub enum SystemError {
+    #[error("I/O error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Invalid value in {0}: {1:?}")]
+    InvalidValue(PathBuf, String),
+    #[error("`{0}` failed: {1}")]
+    Command(String, String),
+}
+
+/// A display backlight from `/sys/class/backlight`.
+#[derive(Debug, Clone)]
+pub struct Backlight {
+    path: PathBuf,
+    max: u32,
+}
+
+impl Backlight {
+    /// The first backlight, `None` if there is none.
+    #[must_use]
+    pub fn find() -> Option<Self> {
+        let mut paths: Vec<_> = std::fs::read_dir(BACKLIGHT_DIR)
+            .ok()?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect();
+        paths.sort();
+        paths.into_iter().find_map(|path| {
+            Self::open(path)
+                .inspect_err(|e| log::warn!("ignoring backlight: {e}"))
+                .ok()
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Fails if `path` is not a backlight directory.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SystemError> {
+        let path = path.as_ref().to_owned();
+        let max = read_value(&path.join("max_brightness"))?;
+        Ok(Self { path, max })
+    }
+
+    /// Brightness in `0.0..=1.0`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the brightness cannot be read.
+    pub fn brightness(&self) -> Result<f32, SystemError> {
+        let value = read_value(&self.path.join("brightness"))?;
+        Ok(value as f32 / self.max.max(1) as f32)
+    }
+
+    /// Sets the brightness, clamped so the screen stays visible.
+    /// Writing usually needs root, or a udev rule for the `video` group.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the brightness cannot be written.
+    pub fn set_brightness(&self, brightness: f32) -> Result<(), SystemError> {
+        let brightness = brightness.clamp(MIN_BRIGHTNESS, 1.0);
+        let value = ((brightness * self.max as f32).round() as u32).max(1);
+        std::fs::write(self.path.join("brightness"), value.to_string())?;
+        Ok(())
+    }
+
+    /// Changes the brightness by `delta`, from `-1.0..=1.0`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the brightness cannot be read or written.
+    pub fn change_brightness(&self, delta: f32) -> Result<(), SystemError> {
+        self.set_brightness(self.brightness()? + delta)
+    }
+}
+
+fn read_value(path: &Path) -> Result<u32, SystemError> {
+    let text = std::fs::read_to_string(path)?;
+    text.trim()
+        .parse()
+        .map_err(|_| SystemError::InvalidValue(path.to_owned(), text))
+}
+
+/// System audio volume of an ALSA mixer control, set through `amixer`.
+#[derive(Debug, Clone)]
+pub struct Volume {
+    control: String,
+    // runs `amixer` for queued changes, started on the first one and shared by clones
+    worker: Arc<OnceLock<Sender<VolumeChange>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum VolumeChange {
+    Percent(i32),
+    ToggleMute,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self::new("Master")
+    }
+}
+
+impl Volume {
+    /// Uses the mixer control `control`, like `Master` or `Speaker`.
+    #[must_use]
+    pub fn new(control: impl Into<String>) -> Self {
+        Self {
+            control: control.into(),
+            worker: Arc::default(),
+        }
+    }
+
+    /// Like [`Volume::change`], but runs on a background thread instead of blocking.
+    /// Changes queued while one is running are combined, errors are logged.
+    pub fn queue_change(&self, percent: i32) {
+        self.queue(VolumeChange::Percent(percent));
+    }
+
+    /// Like [`Volume::toggle_mute`], but runs on a background thread instead of blocking.
+    pub fn queue_toggle_mute(&self) {
+        self.queue(VolumeChange::ToggleMute);
+    }
+
+    fn queue(&self, change: VolumeChange) {
+        let worker = self.worker.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+            let volume = Self::new(self.control.clone());
+            std::thread::spawn(move || {
+                // ends once every clone of the volume is dropped
+                while let Ok(first) = receiver.recv() {
+                    let mut percent = 0;
+                    let mut toggle_mute = false;
+                    for change in std::iter::once(first).chain(receiver.try_iter()) {
+                        match change {
+                            VolumeChange::Percent(change) => percent += change,
+                            VolumeChange::ToggleMute => toggle_mute = !toggle_mute,
+                        }
+                    }
+
+                    let mut result = Ok(());
+                    if toggle_mute {
+                        result = volume.toggle_mute();
+                    }
+                    if percent != 0 {
+                        result = result.and(volume.change(percent));
+                    }
+                    if let Err(e) = result {
+                        log::warn!("failed to change volume: {e}");
+                    }
+                }
+            });
+            sender
+        });
+        let _ = worker.send(change);
+    }
+
+    /// Changes the volume by `percent`, which can be negative.
+    /// Blocks until `amixer` is done.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `amixer` is missing or fails.
+    pub fn change(&self, percent: i32) -> Result<(), SystemError> {
+        let sign = if percent < 0 { '-' } else { '+' };
+        self.amixer(&format!("{}%{sign}", percent.unsigned_abs()))
+    }
+
+    /// Toggles mute.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `amixer` is missing or fails.
+    pub fn toggle_mute(&self) -> Result<(), SystemError> {
+        self.amixer("toggle")
+    }
+
+    fn amixer(&self, value: &str) -> Result<(), SystemError> {
+        let output = Command::new("amixer")
+            .args(["-q", "sset", &self.control, value])
+            .output()?;
+        if !output.status.success() {
+            return Err(SystemError::Command(
+                format!("amixer sset {} {value}", self.control),
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
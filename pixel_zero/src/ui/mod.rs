@@ -0,0 +1,1902 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use glam::{IVec2, UVec2, ivec2, uvec2};
+use num_traits::{Num, NumCast, ToPrimitive};
+use parking_lot::Mutex;
+use strum::IntoEnumIterator as _;
+
+use crate::graphics::frame::DrawCommand;
+use crate::graphics::{Color, Font, Frame, Sprite};
+use crate::input::{Button, Input, TextInput};
+use crate::{HEIGHT, WIDTH};
+
+pub use crate::ui::theme::{Theme, ThemeError};
+
+mod theme;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Id(u64);
+
+impl Id {
+    pub fn new(source: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ui(Arc<Mutex<UiInner>>);
+
+impl Ui {
+    #[must_use]
+    pub fn new(font: Font) -> Self {
+        Self(Arc::new(Mutex::new(UiInner::new(font))))
+    }
+
+    pub fn update_input(&self, input: &Input) {
+        let mut inner = self.0.lock();
+        inner.input = UiInput::from_input(input);
+    }
+
+    /// Starts laying out widgets.
+    ///
+    /// The d-pad moves the focus to the nearest widget in its direction,
+    /// unless the focused widget uses that direction itself, like sliders do with left and right.
+    /// While a modal was open in the last frame, widgets outside of it don't get input.
+    pub fn begin_frame(&self) {
+        let mut inner = self.0.lock();
+        inner.focus.blocked = inner.last_modal_depth > 0;
+        inner.begin_scope();
+        inner.reset_layout();
+        inner.draw_commands.clear();
+        inner.overlays.clear();
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.0.lock();
+        inner.draw_commands.clear();
+        inner.overlays.clear();
+        inner.focus.widget_index = 0;
+        inner.focus.rects.clear();
+        inner.reset_layout();
+    }
+
+    pub fn label(&self, text: &str) {
+        let mut inner = self.0.lock();
+        inner.label(text);
+    }
+
+    pub fn button(&self, text: &str) -> bool {
+        let mut inner = self.0.lock();
+        inner.button(text)
+    }
+
+    pub fn checkbox(&self, text: &str, value: &mut bool) -> bool {
+        let mut inner = self.0.lock();
+        inner.checkbox(text, value)
+    }
+
+    pub fn slider<T>(&self, text: &str, value: &mut T, range: RangeInclusive<T>, speed: T) -> bool
+    where
+        T: Num + Copy + PartialOrd + ToPrimitive + NumCast + std::fmt::Display,
+    {
+        let mut inner = self.0.lock();
+        inner.number(text, value, range, speed)
+    }
+
+    /// On-screen keyboard for entering up to `max_len` characters,
+    /// with a line showing `text` above it.
+    ///
+    /// The d-pad picks a key and A types it, B deletes the last character and Start submits.
+    /// Physical keyboards type directly while it is focused.
+    /// Up from the top row and down from the bottom row move the focus to other widgets.
+    pub fn keyboard(&self, text: &mut String, max_len: usize) -> KeyboardResponse {
+        let mut inner = self.0.lock();
+        inner.keyboard(text, max_len)
+    }
+
+    /// List of `len` rows, of which only the visible ones are laid out,
+    /// so it works for long lists like a game library.
+    ///
+    /// Up and down move `selected`, L and R move a page.
+    /// Returns whether A was pressed on the selected row.
+    pub fn list(
+        &self,
+        id: impl Hash,
+        selected: &mut usize,
+        len: usize,
+        height: u32,
+        label: impl Fn(usize) -> String,
+    ) -> bool {
+        let mut inner = self.0.lock();
+        inner.list(Id::new(id), selected, len, height, label)
+    }
+
+    /// Starts an area of `height` pixels, that scrolls to keep the focused widget inside visible.
+    /// A scrollbar is shown when its widgets don't fit.
+    /// Scroll areas can't be nested.
+    pub fn begin_scroll_area(&self, id: impl Hash, height: u32) {
+        let mut inner = self.0.lock();
+        inner.begin_scroll_area(Id::new(id), height);
+    }
+
+    pub fn end_scroll_area(&self) {
+        let mut inner = self.0.lock();
+        inner.end_scroll_area();
+    }
+
+    /// Picks one of `options` with left and right, wrapping around.
+    pub fn choice(&self, text: &str, selected: &mut usize, options: &[&str]) -> bool {
+        let mut inner = self.0.lock();
+        inner.choice(text, selected, options)
+    }
+
+    /// A row per option, A on one selects it.
+    pub fn radio_group(&self, selected: &mut usize, options: &[&str]) -> bool {
+        let mut inner = self.0.lock();
+        inner.radio_group(selected, options)
+    }
+
+    /// A bar of tabs, switched with L and R no matter which widget is focused,
+    /// unless it is a list, which pages with them.
+    pub fn tabs(&self, selected: &mut usize, tabs: &[&str]) -> bool {
+        let mut inner = self.0.lock();
+        inner.tabs(selected, tabs)
+    }
+
+    /// An on/off switch, flipped with A or set with left and right.
+    pub fn toggle(&self, text: &str, value: &mut bool) -> bool {
+        let mut inner = self.0.lock();
+        inner.toggle(text, value)
+    }
+
+    /// A button showing `sprite` instead of text.
+    pub fn image_button(&self, sprite: &Sprite) -> bool {
+        let mut inner = self.0.lock();
+        inner.image_button(sprite)
+    }
+
+    /// A single line of text, typed into with a physical keyboard while focused.
+    /// Use [`Ui::keyboard`] for devices without one.
+    pub fn text_field(&self, text: &mut String, max_len: usize) -> bool {
+        let mut inner = self.0.lock();
+        inner.text_field(text, max_len)
+    }
+
+    pub fn progress_bar<T>(&self, value: T, range: RangeInclusive<T>)
+    where
+        T: Num + Copy + PartialOrd + ToPrimitive,
+    {
+        let mut inner = self.0.lock();
+        inner.progress_bar(value, range);
+    }
+
+    pub fn separator(&self) {
+        let mut inner = self.0.lock();
+        inner.separator();
+    }
+
+    pub fn spacer(&self, height: u32) {
+        let mut inner = self.0.lock();
+        inner.spacer(height);
+    }
+
+    pub fn begin_columns(&self, count: u32) {
+        let mut inner = self.0.lock();
+        inner.begin_columns(count);
+    }
+
+    pub fn next_column(&self) {
+        let mut inner = self.0.lock();
+        inner.next_column();
+    }
+
+    pub fn end_columns(&self) {
+        let mut inner = self.0.lock();
+        inner.end_columns();
+    }
+
+    pub fn set_layout_width(&self, width: u32) {
+        let mut inner = self.0.lock();
+        inner.theme.layout_width = Some(width.max(1));
+        inner.layout_width = inner.clamp_layout_width(width);
+    }
+
+    pub fn clear_layout_width(&self) {
+        let mut inner = self.0.lock();
+        inner.theme.layout_width = None;
+        inner.layout_width = inner.max_layout_width();
+    }
+
+    pub fn set_padding(&self, padding: i32) {
+        let mut inner = self.0.lock();
+        inner.theme.padding = padding.max(0);
+    }
+
+    pub fn set_spacing(&self, spacing: i32) {
+        let mut inner = self.0.lock();
+        inner.theme.spacing = spacing.max(0);
+    }
+
+    /// Sets whether moving the focus past the last widget in a direction
+    /// continues from the other side.
+    pub fn set_focus_wrap(&self, wrap: FocusWrap) {
+        let mut inner = self.0.lock();
+        inner.focus_wrap = wrap;
+    }
+
+    /// Starts a dialog drawn over everything else, centered on the screen.
+    /// Its widgets have their own focus, and everything else stops getting input while it is open.
+    /// Dialogs can be opened from within dialogs.
+    ///
+    /// Keep calling it every frame while the dialog should stay open.
+    /// It takes input from the frame after it opens, so the press that opened it isn't read again.
+    pub fn begin_modal(&self, id: impl Hash, title: &str) {
+        let mut inner = self.0.lock();
+        inner.begin_modal(Id::new(id), title);
+    }
+
+    /// Ends the dialog, returns whether B was pressed to back out of it.
+    pub fn end_modal(&self) -> bool {
+        let mut inner = self.0.lock();
+        inner.end_modal()
+    }
+
+    /// A dialog asking `message` with Yes and No, like "Quit game?". No is focused at first.
+    pub fn confirm(&self, id: impl Hash, message: &str) -> DialogResult<bool> {
+        let mut inner = self.0.lock();
+        if inner.begin_modal(Id::new(id), message) {
+            // focus "No", so a stray press doesn't confirm
+            inner.focus.frame_focus_index = 1;
+        }
+        inner.begin_columns(2);
+        let yes = inner.button("Yes");
+        inner.next_column();
+        let no = inner.button("No");
+        inner.end_columns();
+
+        if inner.end_modal() {
+            DialogResult::Cancelled
+        } else if yes || no {
+            DialogResult::Done(yes)
+        } else {
+            DialogResult::Pending
+        }
+    }
+
+    /// A dialog with a button per option, returns the index of the chosen one.
+    pub fn popup_menu(&self, id: impl Hash, title: &str, options: &[&str]) -> DialogResult<usize> {
+        let mut inner = self.0.lock();
+        inner.begin_modal(Id::new(id), title);
+        let mut chosen = None;
+        for (index, option) in options.iter().enumerate() {
+            if inner.button(option) {
+                chosen = Some(index);
+            }
+        }
+
+        match (inner.end_modal(), chosen) {
+            (true, _) => DialogResult::Cancelled,
+            (false, Some(index)) => DialogResult::Done(index),
+            (false, None) => DialogResult::Pending,
+        }
+    }
+
+    /// Shows `text` at the bottom of the screen for `duration`, over everything else.
+    /// Toasts don't take focus, newer ones stack on top.
+    pub fn toast(&self, text: &str, duration: Duration) {
+        let mut inner = self.0.lock();
+        inner.toasts.push(Toast {
+            text: text.to_owned(),
+            until: Instant::now() + duration,
+        });
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let mut inner = self.0.lock();
+        // dialogs that weren't ended
+        while !inner.modal_stack.is_empty() {
+            inner.end_modal();
+        }
+        inner.focus.finish();
+        inner.last_modal_depth = std::mem::take(&mut inner.modal_depth);
+        // closed dialogs start over when opened again
+        let open_modals = std::mem::take(&mut inner.open_modals);
+        inner.scopes.retain(|id, _| open_modals.contains(id));
+
+        frame.add_commands(&inner.draw_commands);
+        for overlay in &inner.overlays {
+            frame.add_commands(overlay);
+        }
+
+        let start = inner.draw_commands.len();
+        inner.draw_toasts();
+        frame.add_commands(&inner.draw_commands[start..]);
+        inner.draw_commands.truncate(start);
+    }
+
+    /// The font of the current theme.
+    #[must_use]
+    pub fn font(&self) -> Font {
+        self.0.lock().font().clone()
+    }
+
+    #[must_use]
+    pub fn theme(&self) -> Theme {
+        self.0.lock().theme.clone()
+    }
+
+    /// Replaces the theme of all following widgets, and future frames.
+    pub fn set_theme(&self, theme: Theme) {
+        let mut inner = self.0.lock();
+        inner.theme = theme;
+        inner.apply_layout_width();
+    }
+
+    /// Uses `theme` for the following widgets, until [`Ui::pop_theme`].
+    /// Pushes left at the end of the frame are undone with a warning.
+    /// Overrides of single values can start from [`Ui::theme`]:
+    ///
+    /// ```
+    /// # use pixel_zero::{graphics::Color, ui::{Theme, Ui}};
+    /// # fn delete_button(ui: &Ui) {
+    /// ui.push_theme(Theme { focus_outline: Color::RED, ..ui.theme() });
+    /// ui.button("Delete");
+    /// ui.pop_theme();
+    /// # }
+    /// ```
+    pub fn push_theme(&self, theme: Theme) {
+        let mut inner = self.0.lock();
+        let previous = std::mem::replace(&mut inner.theme, theme);
+        inner.theme_stack.push(previous);
+        inner.apply_layout_width();
+    }
+
+    /// Goes back to the theme before the last [`Ui::push_theme`].
+    pub fn pop_theme(&self) {
+        let mut inner = self.0.lock();
+        if let Some(theme) = inner.theme_stack.pop() {
+            inner.theme = theme;
+            inner.apply_layout_width();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UiInner {
+    font: Font,
+    input: UiInput,
+    // focus of the widgets outside of dialogs, or of the innermost dialog
+    focus: FocusScope,
+    // focus of dialogs while their parent is in `focus`
+    scopes: HashMap<Id, FocusScope>,
+    modal_stack: Vec<ModalState>,
+    // dialogs begun this frame, the others are closed
+    open_modals: Vec<Id>,
+    // depth of the innermost dialog this frame, and last frame
+    modal_depth: usize,
+    last_modal_depth: usize,
+    // draw commands of dialogs, drawn after everything else by depth
+    overlays: Vec<Vec<DrawCommand>>,
+    toasts: Vec<Toast>,
+    theme: Theme,
+    // themes replaced by `push_theme`
+    theme_stack: Vec<Theme>,
+    draw_commands: Vec<DrawCommand>,
+    cursor: IVec2,
+    layout_width: u32,
+    columns: Option<ColumnsState>,
+    focus_wrap: FocusWrap,
+    keyboard: KeyboardState,
+    // pixel offset of scroll areas, and first row of lists
+    scroll: HashMap<Id, i32>,
+    scroll_area: Option<ScrollAreaState>,
+    clip_stack: Vec<(IVec2, UVec2)>,
+}
+
+impl UiInner {
+    fn new(font: Font) -> Self {
+        Self {
+            font,
+            input: UiInput::default(),
+            focus: FocusScope::default(),
+            scopes: HashMap::new(),
+            modal_stack: Vec::new(),
+            open_modals: Vec::new(),
+            modal_depth: 0,
+            last_modal_depth: 0,
+            overlays: Vec::new(),
+            toasts: Vec::new(),
+            theme: Theme::default(),
+            theme_stack: Vec::new(),
+            draw_commands: Vec::new(),
+            cursor: ivec2(0, 0),
+            layout_width: WIDTH / 3,
+            columns: None,
+            focus_wrap: FocusWrap::default(),
+            keyboard: KeyboardState::default(),
+            scroll: HashMap::new(),
+            scroll_area: None,
+            clip_stack: Vec::new(),
+        }
+    }
+}
+
+impl UiInner {
+    fn label(&mut self, text: &str) {
+        let text_size = self.font().text_size(text);
+        let position = self.place_widget(text_size);
+        self.draw_text(text, position);
+    }
+
+    fn button(&mut self, text: &str) -> bool {
+        let is_focused = self.is_focused();
+        let button_size = self.sized_widget(text);
+        let position = self.place_widget(button_size);
+
+        self.draw_widget_background(position, button_size, is_focused);
+        self.draw_centered_text(text, position, button_size);
+
+        if is_focused {
+            self.draw_focus_outline(position, button_size);
+        }
+
+        self.end_widget(position, button_size, FocusCapture::None);
+
+        is_focused && self.input.just_pressed(Button::A)
+    }
+
+    fn checkbox(&mut self, text: &str, value: &mut bool) -> bool {
+        let is_focused = self.is_focused();
+        let box_size = self.theme.checkbox_size;
+        let layout = self.labeled_box_layout(text, uvec2(box_size, box_size));
+
+        self.draw_rect(
+            layout.box_position,
+            layout.box_size,
+            self.theme.widget_border,
+            false,
+        );
+
+        if *value {
+            self.draw_rect(
+                layout.box_position + 2,
+                layout.box_size - 4,
+                self.theme.checkbox_fill,
+                true,
+            );
+        }
+
+        self.draw_text(text, layout.text_position);
+
+        if is_focused {
+            self.draw_labeled_focus_outline(&layout);
+        }
+
+        let changed = if is_focused && self.input.just_pressed(Button::A) {
+            *value = !*value;
+            true
+        } else {
+            false
+        };
+
+        self.end_labeled_widget(&layout, FocusCapture::None);
+        changed
+    }
+
+    fn choice(&mut self, text: &str, selected: &mut usize, options: &[&str]) -> bool {
+        self.label(text);
+
+        let is_focused = self.is_focused();
+        let previous = *selected;
+        *selected = (*selected).min(options.len().saturating_sub(1));
+        if is_focused && !options.is_empty() {
+            if self.input.repeated(Button::Left) {
+                *selected = selected.checked_sub(1).unwrap_or(options.len() - 1);
+            }
+            if self.input.repeated(Button::Right) || self.input.just_pressed(Button::A) {
+                *selected = (*selected + 1) % options.len();
+            }
+        }
+
+        let option = options.get(*selected).copied().unwrap_or_default();
+        let value_text = format!("< {option} >");
+        // full width, so it doesn't change size with the option
+        let size = uvec2(self.layout_width, self.sized_widget(&value_text).y);
+        let position = self.place_widget(size);
+
+        self.draw_widget_background(position, size, is_focused);
+        self.draw_centered_text(&value_text, position, size);
+        if is_focused {
+            self.draw_focus_outline(position, size);
+        }
+
+        self.end_widget(position, size, FocusCapture::Horizontal);
+        *selected != previous
+    }
+
+    fn radio_group(&mut self, selected: &mut usize, options: &[&str]) -> bool {
+        let mut changed = false;
+        for (index, option) in options.iter().enumerate() {
+            let is_focused = self.is_focused();
+            let box_size = self.theme.checkbox_size;
+            let layout = self.labeled_box_layout(option, uvec2(box_size, box_size));
+
+            self.draw_rect(
+                layout.box_position,
+                layout.box_size,
+                self.theme.widget_border,
+                false,
+            );
+            if *selected == index {
+                // smaller than a checkbox fill, to tell them apart
+                self.draw_rect(
+                    layout.box_position + 3,
+                    layout.box_size.saturating_sub(UVec2::splat(6)),
+                    self.theme.checkbox_fill,
+                    true,
+                );
+            }
+            self.draw_text(option, layout.text_position);
+
+            if is_focused {
+                self.draw_labeled_focus_outline(&layout);
+                if self.input.just_pressed(Button::A) && *selected != index {
+                    *selected = index;
+                    changed = true;
+                }
+            }
+            self.end_labeled_widget(&layout, FocusCapture::None);
+        }
+        changed
+    }
+
+    fn tabs(&mut self, selected: &mut usize, tabs: &[&str]) -> bool {
+        let previous = *selected;
+        *selected = (*selected).min(tabs.len().saturating_sub(1));
+        // lists page and text fields type with L and R
+        let uses_shoulders = self
+            .focus
+            .last_rects
+            .get(self.focus.frame_focus_index)
+            .is_some_and(|rect| {
+                matches!(rect.captures, FocusCapture::Vertical | FocusCapture::All)
+            });
+        if !tabs.is_empty() && !uses_shoulders && !self.focus.blocked {
+            if self.input.repeated(Button::L) {
+                *selected = selected.checked_sub(1).unwrap_or(tabs.len() - 1);
+            }
+            if self.input.repeated(Button::R) {
+                *selected = (*selected + 1) % tabs.len();
+            }
+        }
+
+        let height = self.sized_widget("").y;
+        let size = uvec2(self.layout_width, height);
+        let position = self.place_widget(size);
+        let count = tabs.len().max(1) as u32;
+        let tab_width = (size.x / count).max(1);
+        for (index, tab) in tabs.iter().enumerate() {
+            let tab_position = position + ivec2((index as u32 * tab_width).cast_signed(), 0);
+            // the last tab takes the rounding remainder
+            let width = if index as u32 == count - 1 {
+                size.x - tab_width * (count - 1)
+            } else {
+                tab_width
+            };
+            let tab_size = uvec2(width, height);
+            self.draw_widget_background(tab_position, tab_size, index == *selected);
+            self.draw_centered_text(tab, tab_position, tab_size);
+        }
+
+        *selected != previous
+    }
+
+    fn toggle(&mut self, text: &str, value: &mut bool) -> bool {
+        let is_focused = self.is_focused();
+        let previous = *value;
+        if is_focused {
+            if self.input.just_pressed(Button::A) {
+                *value = !*value;
+            }
+            if self.input.just_pressed(Button::Left) {
+                *value = false;
+            }
+            if self.input.just_pressed(Button::Right) {
+                *value = true;
+            }
+        }
+
+        let height = self.theme.checkbox_size;
+        let layout = self.labeled_box_layout(text, uvec2(height * 2, height));
+        let track = if *value {
+            self.theme.progress_fill
+        } else {
+            self.theme.progress_track
+        };
+        self.draw_rect(layout.box_position, layout.box_size, track, true);
+        self.draw_rect(
+            layout.box_position,
+            layout.box_size,
+            self.theme.widget_border,
+            false,
+        );
+        let knob_x = if *value { height.cast_signed() } else { 0 };
+        self.draw_rect(
+            layout.box_position + ivec2(knob_x, 0) + 2,
+            UVec2::splat(height.saturating_sub(4)),
+            self.theme.checkbox_fill,
+            true,
+        );
+        self.draw_text(text, layout.text_position);
+
+        if is_focused {
+            self.draw_labeled_focus_outline(&layout);
+        }
+
+        self.end_labeled_widget(&layout, FocusCapture::Horizontal);
+        *value != previous
+    }
+
+    fn image_button(&mut self, sprite: &Sprite) -> bool {
+        let is_focused = self.is_focused();
+        let pad = self.theme.widget_padding.max(0).cast_unsigned();
+        let size = sprite.size() + pad * 2;
+        let position = self.place_widget(size);
+
+        self.draw_widget_background(position, size, is_focused);
+        self.draw_commands.push(DrawCommand::Sprite {
+            sprite: sprite.clone(),
+            position: position + pad.cast_signed(),
+        });
+        if is_focused {
+            self.draw_focus_outline(position, size);
+        }
+
+        self.end_widget(position, size, FocusCapture::None);
+        is_focused && self.input.just_pressed(Button::A)
+    }
+
+    fn text_field(&mut self, text: &mut String, max_len: usize) -> bool {
+        let is_focused = self.is_focused();
+        let mut changed = false;
+        // captures everything so typing doesn't trigger buttons, but still moves focus with the d-pad
+        if is_focused
+            && self.input.text.is_empty()
+            && let Some(direction) = Direction::ALL
+                .into_iter()
+                .find(|direction| self.input.repeated(direction.button()))
+        {
+            self.focus.focus_move = Some(direction);
+        }
+        if is_focused {
+            for input in &self.input.text {
+                match *input {
+                    TextInput::Char(c) if text.chars().count() < max_len => {
+                        text.push(c);
+                        changed = true;
+                    }
+                    TextInput::Backspace => changed |= text.pop().is_some(),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut line = text.clone();
+        if is_focused && text.chars().count() < max_len {
+            line.push('_');
+        }
+        let height = self.sized_widget("").y;
+        let size = uvec2(self.layout_width, height);
+        let position = self.place_widget(size);
+
+        self.draw_widget_background(position, size, is_focused);
+        self.push_clip(position, size);
+        let pad = self.theme.widget_padding;
+        self.draw_text(&line, position + pad);
+        self.pop_clip();
+        if is_focused {
+            self.draw_focus_outline(position, size);
+        }
+
+        self.end_widget(position, size, FocusCapture::All);
+        changed
+    }
+
+    fn number<T>(&mut self, text: &str, value: &mut T, range: RangeInclusive<T>, speed: T) -> bool
+    where
+        T: Num + Copy + PartialOrd + ToPrimitive + NumCast + std::fmt::Display,
+    {
+        self.label(text);
+
+        let is_focused = self.is_focused();
+        let value_text = format!("< {} >", value);
+        let size = self.sized_widget(&value_text);
+        let position = self.place_widget(size);
+
+        self.draw_widget_background(position, size, is_focused);
+
+        self.draw_centered_text(&value_text, position, size);
+
+        if is_focused {
+            self.draw_focus_outline(position, size);
+        }
+
+        let changed = if is_focused {
+            apply_step_input(&self.input, value, range, speed)
+        } else {
+            false
+        };
+
+        self.end_widget(position, size, FocusCapture::Horizontal);
+        changed
+    }
+
+    fn keyboard(&mut self, text: &mut String, max_len: usize) -> KeyboardResponse {
+        let is_focused = self.is_focused();
+        let glyph_size = self.font().glyph_size();
+        let pad = self.theme.widget_padding.max(0).cast_unsigned();
+        let key_size = glyph_size + pad * 2;
+        let size = uvec2(
+            key_size.x * KEYBOARD_COLUMNS as u32,
+            key_size.y * (KEYBOARD_ROWS as u32 + 1) + self.theme.spacing.max(0).cast_unsigned(),
+        );
+        let position = self.place_widget(size);
+
+        let response = if is_focused {
+            self.keyboard_input(text, max_len)
+        } else {
+            KeyboardResponse::None
+        };
+
+        // text line, with a cursor while there is room
+        let line_size = uvec2(size.x, key_size.y);
+        self.draw_widget_background(position, line_size, false);
+        let mut line = text.clone();
+        if text.chars().count() < max_len {
+            line.push('_');
+        }
+        let text_position = position + pad.cast_signed();
+        self.draw_text(&line, text_position);
+
+        let keys_origin = position + ivec2(0, (key_size.y.cast_signed()) + self.theme.spacing);
+        for row in 0..KEYBOARD_ROWS {
+            let mut column = 0;
+            while column < KEYBOARD_COLUMNS {
+                let key = KeyboardKey::at(column, row);
+                let span = key.columns(column);
+                let key_position = keys_origin
+                    + ivec2(
+                        (span.start as u32 * key_size.x).cast_signed(),
+                        (row as u32 * key_size.y).cast_signed(),
+                    );
+                let key_size = uvec2(key_size.x * span.len() as u32, key_size.y);
+                let selected =
+                    is_focused && span.contains(&self.keyboard.column) && row == self.keyboard.row;
+                let active = key == KeyboardKey::Shift && self.keyboard.shift;
+
+                self.draw_widget_background(key_position, key_size, selected || active);
+                self.draw_centered_text(&key.label(self.keyboard.shift), key_position, key_size);
+                if selected {
+                    self.draw_focus_outline(key_position, key_size);
+                }
+                column = span.end;
+            }
+        }
+
+        self.end_widget(position, size, FocusCapture::All);
+        response
+    }
+
+    fn keyboard_input(&mut self, text: &mut String, max_len: usize) -> KeyboardResponse {
+        let mut response = KeyboardResponse::None;
+        let push = |text: &mut String, c: char, response: &mut KeyboardResponse| {
+            if text.chars().count() < max_len {
+                text.push(c);
+                *response = KeyboardResponse::Changed;
+            }
+        };
+
+        // typed keys are also mapped to buttons, which would type twice
+        if !self.input.text.is_empty() {
+            for input in &self.input.text {
+                match *input {
+                    TextInput::Char(c) => push(text, c, &mut response),
+                    TextInput::Backspace => {
+                        if text.pop().is_some() {
+                            response = KeyboardResponse::Changed;
+                        }
+                    }
+                    TextInput::Enter => return KeyboardResponse::Submitted,
+                }
+            }
+            return response;
+        }
+
+        let state = &mut self.keyboard;
+        if self.input.repeated(Button::Up) {
+            if state.row == 0 {
+                self.focus.focus_move = Some(Direction::Up);
+            } else {
+                state.row -= 1;
+            }
+        }
+        if self.input.repeated(Button::Down) {
+            if state.row == KEYBOARD_ROWS - 1 {
+                self.focus.focus_move = Some(Direction::Down);
+            } else {
+                state.row += 1;
+            }
+        }
+        if self.input.repeated(Button::Left) {
+            let start = KeyboardKey::at(state.column, state.row)
+                .columns(state.column)
+                .start;
+            state.column = start.checked_sub(1).unwrap_or(KEYBOARD_COLUMNS - 1);
+        }
+        if self.input.repeated(Button::Right) {
+            let end = KeyboardKey::at(state.column, state.row)
+                .columns(state.column)
+                .end;
+            state.column = end % KEYBOARD_COLUMNS;
+        }
+
+        if self.input.repeated(Button::B) && text.pop().is_some() {
+            response = KeyboardResponse::Changed;
+        }
+        if self.input.just_pressed(Button::A) {
+            match KeyboardKey::at(state.column, state.row) {
+                KeyboardKey::Char(lower, upper) => {
+                    let c = if state.shift { upper } else { lower };
+                    push(text, c, &mut response);
+                }
+                KeyboardKey::Shift => state.shift = !state.shift,
+                KeyboardKey::Space => push(text, ' ', &mut response),
+                KeyboardKey::Delete => {
+                    if text.pop().is_some() {
+                        response = KeyboardResponse::Changed;
+                    }
+                }
+                KeyboardKey::Done => return KeyboardResponse::Submitted,
+            }
+        }
+        if self.input.just_pressed(Button::Start) {
+            return KeyboardResponse::Submitted;
+        }
+
+        response
+    }
+
+    fn list(
+        &mut self,
+        id: Id,
+        selected: &mut usize,
+        len: usize,
+        height: u32,
+        label: impl Fn(usize) -> String,
+    ) -> bool {
+        let is_focused = self.is_focused();
+        let pad = self.theme.widget_padding;
+        let row_height = (self.font().glyph_size().y.cast_signed() + pad * 2).max(1);
+        let rows = (height.cast_signed() / row_height).max(1);
+        let size = uvec2(self.layout_width, (rows * row_height).cast_unsigned());
+        let position = self.place_widget(size);
+        let rows = rows.cast_unsigned() as usize;
+
+        *selected = (*selected).min(len.saturating_sub(1));
+        let mut activated = false;
+        if is_focused && len > 0 {
+            // leaving the list at either end moves the focus on
+            if self.input.repeated(Button::Up) {
+                match selected.checked_sub(1) {
+                    Some(previous) => *selected = previous,
+                    None => self.focus.focus_move = Some(Direction::Up),
+                }
+            }
+            if self.input.repeated(Button::Down) {
+                if *selected + 1 < len {
+                    *selected += 1;
+                } else {
+                    self.focus.focus_move = Some(Direction::Down);
+                }
+            }
+            if self.input.repeated(Button::L) {
+                *selected = selected.saturating_sub(rows);
+            }
+            if self.input.repeated(Button::R) {
+                *selected = (*selected + rows).min(len - 1);
+            }
+            activated = self.input.just_pressed(Button::A);
+        }
+
+        let mut first = self
+            .scroll
+            .get(&id)
+            .map_or(0, |first| (*first).max(0).cast_unsigned() as usize);
+        if *selected < first {
+            first = *selected;
+        } else if *selected >= first + rows {
+            first = *selected + 1 - rows;
+        }
+        first = first.min(len.saturating_sub(rows));
+        self.scroll.insert(id, first as i32);
+
+        self.draw_rect(position, size, self.theme.widget_bg, true);
+        let scrollbar_width = if len > rows {
+            self.theme.scrollbar_width
+        } else {
+            0
+        };
+        let row_width = size.x.saturating_sub(scrollbar_width).max(1);
+
+        self.push_clip(position, uvec2(row_width, size.y));
+        for (row, index) in (first..len.min(first + rows)).enumerate() {
+            let row_position = position + ivec2(0, row as i32 * row_height);
+            let row_size = uvec2(row_width, row_height.cast_unsigned());
+            if index == *selected {
+                self.draw_rect(row_position, row_size, self.theme.widget_bg_focused, true);
+                if is_focused {
+                    self.draw_focus_outline(row_position, row_size);
+                }
+            }
+            self.draw_text(&label(index), row_position + pad);
+        }
+        self.pop_clip();
+
+        if len > rows {
+            let bar_position = position + ivec2(row_width.cast_signed(), 0);
+            self.draw_scrollbar(bar_position, size.y, first, rows, len);
+        }
+        self.draw_rect(position, size, self.theme.widget_border, false);
+
+        self.end_widget(position, size, FocusCapture::Vertical);
+        activated
+    }
+
+    fn begin_scroll_area(&mut self, id: Id, height: u32) {
+        if self.scroll_area.is_some() {
+            return;
+        }
+
+        let size = uvec2(self.layout_width, height.max(1));
+        let origin = self.place_widget(size);
+        let offset = self.scroll.get(&id).copied().unwrap_or(0);
+        self.push_clip(origin, size);
+
+        self.scroll_area = Some(ScrollAreaState {
+            id,
+            origin,
+            size,
+            offset,
+            focused: None,
+            previous_cursor: self.cursor,
+            previous_layout_width: self.layout_width,
+        });
+        self.cursor = origin - ivec2(0, offset);
+        let scrollbar_space =
+            self.theme.scrollbar_width + self.theme.spacing.max(0).cast_unsigned();
+        self.layout_width = size.x.saturating_sub(scrollbar_space).max(1);
+    }
+
+    fn end_scroll_area(&mut self) {
+        let Some(area) = self.scroll_area.take() else {
+            return;
+        };
+        self.pop_clip();
+
+        let content_top = area.origin.y - area.offset;
+        let content_height = (self.cursor.y - self.theme.spacing - content_top).max(0);
+        let view_height = area.size.y.cast_signed();
+        let max_offset = (content_height - view_height).max(0);
+
+        // takes effect next frame, as everything inside is already laid out
+        let mut offset = area.offset;
+        if let Some((top, bottom)) = area.focused {
+            if top < offset {
+                offset = top;
+            } else if bottom > offset + view_height {
+                offset = bottom - view_height;
+            }
+        }
+        let offset = offset.clamp(0, max_offset);
+        self.scroll.insert(area.id, offset);
+
+        if max_offset > 0 {
+            let bar_position = area.origin
+                + ivec2(
+                    (area.size.x.saturating_sub(self.theme.scrollbar_width)).cast_signed(),
+                    0,
+                );
+            self.draw_scrollbar(
+                bar_position,
+                area.size.y,
+                offset.cast_unsigned() as usize,
+                area.size.y as usize,
+                content_height.cast_unsigned() as usize,
+            );
+        }
+
+        self.cursor = area.previous_cursor;
+        self.layout_width = area.previous_layout_width;
+    }
+
+    /// Vertical scrollbar showing `visible` of `total`, starting at `offset`.
+    fn draw_scrollbar(
+        &mut self,
+        position: IVec2,
+        height: u32,
+        offset: usize,
+        visible: usize,
+        total: usize,
+    ) {
+        let width = self.theme.scrollbar_width.max(1);
+        self.draw_rect(
+            position,
+            uvec2(width, height),
+            self.theme.scrollbar_track,
+            true,
+        );
+
+        let total = total.max(1) as f32;
+        let thumb_height = ((visible as f32 / total) * height as f32).max(2.0) as u32;
+        let thumb_height = thumb_height.min(height);
+        let max_offset = (total - visible as f32).max(1.0);
+        let thumb_y = (offset as f32 / max_offset * (height - thumb_height) as f32) as i32;
+        self.draw_rect(
+            position + ivec2(0, thumb_y),
+            uvec2(width, thumb_height),
+            self.theme.scrollbar_thumb,
+            true,
+        );
+    }
+
+    fn push_clip(&mut self, position: IVec2, size: UVec2) {
+        // nested clips only draw where both overlap
+        let (position, size) = match self.clip_stack.last() {
+            Some((outer_position, outer_size)) => {
+                let start = position.max(*outer_position);
+                let end = (position + size.as_ivec2()).min(*outer_position + outer_size.as_ivec2());
+                (start, (end - start).max(IVec2::ZERO).as_uvec2())
+            }
+            None => (position, size),
+        };
+        self.clip_stack.push((position, size));
+        self.draw_commands
+            .push(DrawCommand::Clip(Some((position, size))));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+        self.draw_commands
+            .push(DrawCommand::Clip(self.clip_stack.last().copied()));
+    }
+
+    fn progress_bar<T>(&mut self, value: T, range: RangeInclusive<T>)
+    where
+        T: Num + Copy + PartialOrd + ToPrimitive,
+    {
+        let (min, max) = normalized_range(range);
+        let Some(min_f) = min.to_f32() else {
+            return;
+        };
+        let Some(max_f) = max.to_f32() else {
+            return;
+        };
+        let Some(value_f) = value.to_f32() else {
+            return;
+        };
+        let range_size = (max_f - min_f).max(0.0001);
+        let normalized = ((value_f - min_f) / range_size).clamp(0.0, 1.0);
+        let size = uvec2(self.layout_width, self.theme.progress_height);
+        let position = self.place_widget(size);
+        let fill_width = (normalized * size.x as f32) as u32;
+
+        self.draw_rect(position, size, self.theme.progress_track, true);
+
+        if fill_width > 0 {
+            self.draw_rect(
+                position,
+                uvec2(fill_width, size.y),
+                self.theme.progress_fill,
+                true,
+            );
+        }
+
+        self.draw_rect(position, size, self.theme.widget_border, false);
+    }
+
+    fn separator(&mut self) {
+        let size = uvec2(self.layout_width, self.theme.separator_thickness.max(1));
+        let position = self.place_widget(size);
+        self.draw_rect(position, size, self.theme.separator, true);
+    }
+
+    fn spacer(&mut self, height: u32) {
+        let size = uvec2(self.layout_width, height.max(1));
+        self.place_widget(size);
+    }
+
+    /// Records the rectangle of a focusable widget, and moves on to the next one.
+    fn end_widget(&mut self, position: IVec2, size: UVec2, captures: FocusCapture) {
+        if self.focus.widget_index == self.focus.frame_focus_index
+            && let Some(area) = &mut self.scroll_area
+        {
+            let top = position.y - (area.origin.y - area.offset);
+            area.focused = Some((top, top + size.y.cast_signed()));
+        }
+
+        self.focus.rects.push(FocusRect {
+            position,
+            size,
+            captures,
+        });
+        self.focus.widget_index += 1;
+    }
+
+    fn is_focused(&self) -> bool {
+        !self.focus.blocked && self.focus.widget_index == self.focus.frame_focus_index
+    }
+
+    /// Moves the focus of the current scope with the d-pad, and starts laying out its widgets.
+    ///
+    /// The d-pad moves the focus to the nearest widget in its direction,
+    /// unless the focused widget uses that direction itself, like sliders do with left and right.
+    fn begin_scope(&mut self) {
+        let mut focus_index = self.focus.focus_index;
+        if !self.focus.blocked {
+            let captures = self.focused_captures();
+            // typed keys are also mapped to buttons, which shouldn't switch tabs or close dialogs
+            if captures == FocusCapture::All && !self.input.text.is_empty() {
+                for button in [
+                    Button::L,
+                    Button::R,
+                    Button::B,
+                    Button::Start,
+                    Button::Select,
+                ] {
+                    self.input.release(button);
+                }
+            }
+            let mut moves: Vec<_> = self.focus.focus_move.take().into_iter().collect();
+            moves.extend(Direction::ALL.into_iter().filter(|direction| {
+                self.input.repeated(direction.button()) && !captures.contains(*direction)
+            }));
+            for direction in moves {
+                if let Some(index) = self.find_focus(focus_index, direction) {
+                    focus_index = index;
+                }
+            }
+        }
+
+        self.focus.frame_focus_index = focus_index;
+        self.focus.widget_index = 0;
+        self.focus.rects.clear();
+    }
+
+    /// What the focused widget of the current scope used last frame.
+    fn focused_captures(&self) -> FocusCapture {
+        self.focus
+            .last_rects
+            .get(self.focus.focus_index)
+            .map_or(FocusCapture::None, |rect| rect.captures)
+    }
+
+    /// Returns whether the dialog was opened this frame.
+    fn begin_modal(&mut self, id: Id, title: &str) -> bool {
+        let depth = self.modal_stack.len() + 1;
+        self.modal_depth = self.modal_depth.max(depth);
+        self.open_modals.push(id);
+
+        let opened = !self.scopes.contains_key(&id);
+        let mut scope = self.scopes.remove(&id).unwrap_or_default();
+        // the press that opened the dialog doesn't count for it,
+        // and a dialog opened from this one takes the input
+        scope.blocked = opened || self.last_modal_depth > depth;
+        let parent = std::mem::replace(&mut self.focus, scope);
+        self.begin_scope();
+
+        // the keyboard deletes with B
+        let cancelled = !self.focus.blocked
+            && self.input.just_pressed(Button::B)
+            && self.focused_captures() != FocusCapture::All;
+
+        let width = self
+            .theme
+            .layout_width
+            .unwrap_or(WIDTH * 2 / 3)
+            .min(self.max_layout_width())
+            .max(1);
+        let padding = self.theme.padding;
+        self.modal_stack.push(ModalState {
+            id,
+            parent,
+            cancelled,
+            width,
+            commands_start: self.draw_commands.len(),
+            previous_cursor: self.cursor,
+            previous_layout_width: self.layout_width,
+            previous_columns: self.columns.take(),
+            previous_scroll_area: self.scroll_area.take(),
+            previous_clip_stack: std::mem::take(&mut self.clip_stack),
+        });
+
+        // laid out from the top, and moved to the center once the height is known
+        self.cursor = ivec2(
+            (WIDTH.cast_signed() - width.cast_signed()) / 2 + padding,
+            padding,
+        );
+        self.layout_width = width
+            .saturating_sub((padding * 2).max(0).cast_unsigned())
+            .max(1);
+        self.label(title);
+        self.separator();
+        opened
+    }
+
+    fn end_modal(&mut self) -> bool {
+        let Some(modal) = self.modal_stack.pop() else {
+            return false;
+        };
+        while !self.clip_stack.is_empty() {
+            self.pop_clip();
+        }
+
+        let padding = self.theme.padding;
+        let height = (self.cursor.y - self.theme.spacing + padding).max(1);
+        let offset = ivec2(0, ((HEIGHT.cast_signed() - height) / 2).max(0));
+        let position = ivec2((WIDTH.cast_signed() - modal.width.cast_signed()) / 2, 0);
+        let size = uvec2(modal.width, height.cast_unsigned());
+
+        let mut commands = vec![
+            // dims everything below
+            DrawCommand::Rect {
+                position: IVec2::ZERO,
+                size: uvec2(WIDTH, HEIGHT),
+                color: self.theme.modal_dim,
+                filled: true,
+            },
+            DrawCommand::Rect {
+                position: position + offset,
+                size,
+                color: self.theme.widget_bg,
+                filled: true,
+            },
+            DrawCommand::Rect {
+                position: position + offset,
+                size,
+                color: self.theme.widget_border,
+                filled: false,
+            },
+        ];
+        commands.extend(
+            self.draw_commands
+                .drain(modal.commands_start..)
+                .map(|command| command.translated(offset)),
+        );
+        let depth = self.modal_stack.len();
+        if self.overlays.len() <= depth {
+            self.overlays.resize_with(depth + 1, Vec::new);
+        }
+        self.overlays[depth].extend(commands);
+
+        self.focus.finish();
+        let scope = std::mem::replace(&mut self.focus, modal.parent);
+        // widgets after the dialog don't get input either
+        self.focus.blocked = true;
+        self.scopes.insert(modal.id, scope);
+        self.cursor = modal.previous_cursor;
+        self.layout_width = modal.previous_layout_width;
+        self.columns = modal.previous_columns;
+        self.scroll_area = modal.previous_scroll_area;
+        self.clip_stack = modal.previous_clip_stack;
+
+        modal.cancelled
+    }
+
+    fn draw_toasts(&mut self) {
+        let now = Instant::now();
+        self.toasts.retain(|toast| toast.until > now);
+
+        let padding = self.theme.padding;
+        let mut bottom = HEIGHT.cast_signed() - padding;
+        let toasts = std::mem::take(&mut self.toasts);
+        for toast in toasts.iter().rev() {
+            let size = self.sized_widget(&toast.text);
+            bottom -= size.y.cast_signed();
+            let position = ivec2((WIDTH.cast_signed() - size.x.cast_signed()) / 2, bottom);
+            self.draw_widget_background(position, size, false);
+            self.draw_centered_text(&toast.text, position, size);
+            bottom -= self.theme.spacing;
+        }
+        self.toasts = toasts;
+    }
+
+    /// The widget closest to the focused one in `direction`, from the last frame.
+    fn find_focus(&self, focus_index: usize, direction: Direction) -> Option<usize> {
+        let rects = &self.focus.last_rects;
+        let from = rects.get(focus_index)?.center();
+        let nearest = |from: IVec2, skip_focused: bool| {
+            rects
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !skip_focused || *index != focus_index)
+                .filter_map(|(index, rect)| {
+                    let offset = rect.center() - from;
+                    let (along, across) = direction.split(offset);
+                    // off axis distance counts double, so widgets in line are preferred
+                    (along > 0).then_some((index, along + across.abs() * 2))
+                })
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(index, _)| index)
+        };
+
+        nearest(from, true).or_else(|| {
+            if !self.focus_wrap.wraps(direction) {
+                return None;
+            }
+            // continue from beyond the opposite edge of the screen
+            let screen = ivec2(WIDTH.cast_signed(), HEIGHT.cast_signed());
+            let (along, _) = direction.split(screen);
+            let from = from - direction.offset() * along.abs();
+            nearest(from, false)
+        })
+    }
+
+    fn sized_widget(&self, text: &str) -> UVec2 {
+        let text_size = self.font().text_size(text);
+        let pad = self.theme.widget_padding;
+        let min_height = self.font().glyph_size().y.cast_signed();
+        let height = (text_size.y.cast_signed() + pad * 2).max(min_height);
+        let width = (text_size.x.cast_signed() + pad * 2)
+            .min(self.layout_width.cast_signed())
+            .max(1);
+        uvec2(width.cast_unsigned(), height.cast_unsigned().max(1))
+    }
+
+    fn place_widget(&mut self, size: UVec2) -> IVec2 {
+        let position = self.cursor;
+        self.cursor.y = self.clamp_y(self.cursor.y + size.y.cast_signed() + self.theme.spacing);
+
+        if let Some(columns) = &mut self.columns {
+            columns.max_y = columns.max_y.max(self.cursor.y);
+        }
+
+        position
+    }
+
+    fn clamp_y(&self, y: i32) -> i32 {
+        // scroll areas grow past the screen
+        if self.scroll_area.is_some() {
+            return y;
+        }
+        y.min(HEIGHT.cast_signed() - self.theme.padding)
+    }
+
+    fn begin_columns(&mut self, count: u32) {
+        if count < 2 || self.columns.is_some() {
+            return;
+        }
+        let spacing = self.theme.spacing.max(0).cast_unsigned();
+        let total_spacing = spacing.saturating_mul(count.saturating_sub(1));
+        let available = self.layout_width.saturating_sub(total_spacing).max(1);
+        let column_width = (available / count).max(1);
+        self.columns = Some(ColumnsState {
+            count,
+            column_width,
+            column_index: 0,
+            origin: self.cursor,
+            max_y: self.cursor.y,
+            previous_layout_width: self.layout_width,
+        });
+        self.layout_width = column_width;
+    }
+
+    fn next_column(&mut self) {
+        let Some(columns) = &mut self.columns else {
+            return;
+        };
+
+        columns.max_y = columns.max_y.max(self.cursor.y);
+        columns.column_index = (columns.column_index + 1).min(columns.count - 1);
+        let spacing = self.theme.spacing.max(0);
+        let offset =
+            columns.column_index.cast_signed() * (columns.column_width.cast_signed() + spacing);
+        self.cursor = ivec2(columns.origin.x + offset, columns.origin.y);
+    }
+
+    fn end_columns(&mut self) {
+        let Some(columns) = self.columns.take() else {
+            return;
+        };
+
+        let max_y = columns.max_y.max(self.cursor.y);
+        let next_y = self.clamp_y(max_y + self.theme.spacing);
+        self.layout_width = columns.previous_layout_width;
+        self.cursor = ivec2(columns.origin.x, next_y);
+    }
+
+    fn reset_layout(&mut self) {
+        // pushes without a pop don't leak into the next frame
+        if !self.theme_stack.is_empty() {
+            log::warn!("{} push_theme without pop_theme", self.theme_stack.len());
+            self.theme = self.theme_stack.swap_remove(0);
+            self.theme_stack.clear();
+        }
+        let padding = self.theme.padding;
+        self.cursor = ivec2(padding, padding);
+        self.columns = None;
+        self.scroll_area = None;
+        self.apply_layout_width();
+        self.clip_stack.clear();
+    }
+
+    /// Uses the layout width of the theme, unless columns or a scroll area set their own.
+    fn apply_layout_width(&mut self) {
+        if self.columns.is_some() || self.scroll_area.is_some() {
+            return;
+        }
+        self.layout_width = self
+            .theme
+            .layout_width
+            .map(|width| self.clamp_layout_width(width))
+            .unwrap_or_else(|| self.max_layout_width());
+    }
+
+    fn font(&self) -> &Font {
+        self.theme.font.as_ref().unwrap_or(&self.font)
+    }
+
+    fn draw_focus_outline(&mut self, position: IVec2, size: UVec2) {
+        self.draw_rect(position, size, self.theme.focus_outline, false);
+    }
+
+    fn draw_widget_background(&mut self, position: IVec2, size: UVec2, focused: bool) {
+        let fill = if focused {
+            self.theme.widget_bg_focused
+        } else {
+            self.theme.widget_bg
+        };
+        self.draw_rect(position, size, fill, true);
+        self.draw_rect(position, size, self.theme.widget_border, false);
+    }
+
+    fn draw_centered_text(&mut self, text: &str, position: IVec2, size: UVec2) {
+        let text_size = self.font().text_size(text);
+        let diff_x = size.x.cast_signed() - text_size.x.cast_signed();
+        let offset_x = if diff_x > 0 { (diff_x - 1) / 2 } else { 0 };
+        let text_x = position.x + offset_x;
+        let text_y = position.y + ((size.y.cast_signed() - text_size.y.cast_signed()) / 2).max(0);
+        self.draw_text(text, ivec2(text_x, text_y));
+    }
+
+    fn labeled_box_layout(&mut self, text: &str, box_size: UVec2) -> LabeledBoxLayout {
+        let text_size = self.font().text_size(text);
+        let row_height = box_size.y.max(text_size.y).cast_signed();
+        let width = (box_size.x.cast_signed() + self.theme.spacing + text_size.x.cast_signed() + 1)
+            .max(box_size.x.cast_signed())
+            .cast_unsigned();
+        let width = width.min(self.layout_width).max(1);
+        let position = self.place_widget(uvec2(width, row_height.cast_unsigned()));
+        let text_x = position.x + box_size.x.cast_signed() + self.theme.spacing;
+        let text_y = position.y + ((row_height - text_size.y.cast_signed()) / 2).max(0);
+
+        LabeledBoxLayout {
+            position,
+            box_position: position,
+            box_size,
+            text_position: ivec2(text_x, text_y),
+            width,
+            row_height,
+        }
+    }
+
+    fn draw_labeled_focus_outline(&mut self, layout: &LabeledBoxLayout) {
+        let outline_offset = ivec2(-1, -1);
+        let outline_size = uvec2(
+            (layout.width.cast_signed() + 2).cast_unsigned(),
+            (layout.row_height + 1).cast_unsigned(),
+        );
+        self.draw_focus_outline(layout.position + outline_offset, outline_size);
+    }
+
+    fn end_labeled_widget(&mut self, layout: &LabeledBoxLayout, captures: FocusCapture) {
+        self.end_widget(
+            layout.position,
+            uvec2(layout.width, layout.row_height.cast_unsigned()),
+            captures,
+        );
+    }
+
+    fn draw_rect(&mut self, position: IVec2, size: UVec2, color: Color, filled: bool) {
+        self.draw_commands.push(DrawCommand::Rect {
+            position,
+            size,
+            color,
+            filled,
+        });
+    }
+
+    fn draw_text(&mut self, text: &str, position: IVec2) {
+        self.draw_commands.push(DrawCommand::Text {
+            font: self.font().clone(),
+            text: text.to_owned(),
+            position,
+        });
+    }
+
+    fn max_layout_width(&self) -> u32 {
+        let padding = self.theme.padding;
+        WIDTH.saturating_sub((padding * 2).max(0).cast_unsigned())
+    }
+
+    fn clamp_layout_width(&self, width: u32) -> u32 {
+        width.clamp(1, self.max_layout_width().max(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LabeledBoxLayout {
+    position: IVec2,
+    box_position: IVec2,
+    box_size: UVec2,
+    text_position: IVec2,
+    width: u32,
+    row_height: i32,
+}
+
+/// Outcome of a dialog like [`Ui::confirm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogResult<T> {
+    /// Still open.
+    Pending,
+    Done(T),
+    /// Closed with B.
+    Cancelled,
+}
+
+/// Focus of a group of widgets, the ones outside of dialogs or those of a dialog.
+#[derive(Debug, Default)]
+struct FocusScope {
+    focus_index: usize,
+    frame_focus_index: usize,
+    widget_index: usize,
+    // rectangles of focusable widgets by widget index, for spatial navigation
+    rects: Vec<FocusRect>,
+    last_rects: Vec<FocusRect>,
+    // focus movement requested by a widget, applied in the next frame
+    focus_move: Option<Direction>,
+    // a dialog above has the input
+    blocked: bool,
+}
+
+impl FocusScope {
+    /// Keeps the focus on an existing widget, for the next frame.
+    fn finish(&mut self) {
+        let widget_count = self.widget_index;
+        if widget_count > 0 {
+            self.focus_index = self.frame_focus_index.min(widget_count - 1);
+        } else {
+            self.focus_index = 0;
+        }
+        self.last_rects = std::mem::take(&mut self.rects);
+    }
+}
+
+#[derive(Debug)]
+struct ModalState {
+    id: Id,
+    parent: FocusScope,
+    cancelled: bool,
+    width: u32,
+    // where the dialog's draw commands start, to move them to an overlay
+    commands_start: usize,
+    previous_cursor: IVec2,
+    previous_layout_width: u32,
+    previous_columns: Option<ColumnsState>,
+    previous_scroll_area: Option<ScrollAreaState>,
+    previous_clip_stack: Vec<(IVec2, UVec2)>,
+}
+
+#[derive(Debug)]
+struct Toast {
+    text: String,
+    until: Instant,
+}
+
+/// Which directions focus movement continues from the other side of the screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FocusWrap {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl FocusWrap {
+    fn wraps(self, direction: Direction) -> bool {
+        match self {
+            Self::None => false,
+            Self::Horizontal => matches!(direction, Direction::Left | Direction::Right),
+            Self::Vertical => matches!(direction, Direction::Up | Direction::Down),
+            Self::Both => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    fn button(self) -> Button {
+        match self {
+            Self::Up => Button::Up,
+            Self::Down => Button::Down,
+            Self::Left => Button::Left,
+            Self::Right => Button::Right,
+        }
+    }
+
+    fn offset(self) -> IVec2 {
+        match self {
+            Self::Up => ivec2(0, -1),
+            Self::Down => ivec2(0, 1),
+            Self::Left => ivec2(-1, 0),
+            Self::Right => ivec2(1, 0),
+        }
+    }
+
+    /// Splits `offset` into the distance along this direction, and across it.
+    fn split(self, offset: IVec2) -> (i32, i32) {
+        match self {
+            Self::Up => (-offset.y, offset.x),
+            Self::Down => (offset.y, offset.x),
+            Self::Left => (-offset.x, offset.y),
+            Self::Right => (offset.x, offset.y),
+        }
+    }
+}
+
+/// Directions a focused widget uses itself, instead of moving the focus.
+/// Widgets capturing everything also keep B from closing dialogs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FocusCapture {
+    None,
+    Horizontal,
+    Vertical,
+    All,
+}
+
+impl FocusCapture {
+    fn contains(self, direction: Direction) -> bool {
+        match self {
+            Self::None => false,
+            Self::Horizontal => matches!(direction, Direction::Left | Direction::Right),
+            Self::Vertical => matches!(direction, Direction::Up | Direction::Down),
+            Self::All => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FocusRect {
+    position: IVec2,
+    size: UVec2,
+    captures: FocusCapture,
+}
+
+impl FocusRect {
+    fn center(&self) -> IVec2 {
+        self.position + (self.size / 2).as_ivec2()
+    }
+}
+
+/// What happened to the text of an on-screen keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardResponse {
+    None,
+    Changed,
+    /// Done or Start was pressed, or Enter on a physical keyboard.
+    Submitted,
+}
+
+const KEYBOARD_COLUMNS: usize = 10;
+// the last row has the special keys
+const KEYBOARD_ROWS: usize = 5;
+const KEYBOARD_CHARS: [(&str, &str); KEYBOARD_ROWS - 1] = [
+    ("1234567890", "!@#$%&*()?"),
+    ("qwertyuiop", "QWERTYUIOP"),
+    ("asdfghjkl-", "ASDFGHJKL_"),
+    ("zxcvbnm.,'", "ZXCVBNM:;!"),
+];
+
+/// Cursor of the on-screen keyboard, shared by all keyboards.
+#[derive(Debug, Default)]
+struct KeyboardState {
+    column: usize,
+    row: usize,
+    shift: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyboardKey {
+    Char(char, char),
+    Shift,
+    Space,
+    Delete,
+    Done,
+}
+
+impl KeyboardKey {
+    fn at(column: usize, row: usize) -> Self {
+        if let Some((lower, upper)) = KEYBOARD_CHARS.get(row) {
+            let lower = lower.chars().nth(column).unwrap_or(' ');
+            let upper = upper.chars().nth(column).unwrap_or(' ');
+            return Self::Char(lower, upper);
+        }
+
+        match column {
+            0..2 => Self::Shift,
+            2..6 => Self::Space,
+            6..8 => Self::Delete,
+            _ => Self::Done,
+        }
+    }
+
+    /// Columns covered by the key at `column`.
+    fn columns(self, column: usize) -> std::ops::Range<usize> {
+        match self {
+            Self::Char(..) => column..column + 1,
+            Self::Shift => 0..2,
+            Self::Space => 2..6,
+            Self::Delete => 6..8,
+            Self::Done => 8..KEYBOARD_COLUMNS,
+        }
+    }
+
+    fn label(self, shift: bool) -> String {
+        match self {
+            Self::Char(lower, upper) => if shift { upper } else { lower }.to_string(),
+            Self::Shift => "Aa".to_owned(),
+            Self::Space => "Space".to_owned(),
+            Self::Delete => "Del".to_owned(),
+            Self::Done => "OK".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct UiInput {
+    just_pressed: [bool; Button::BUTTON_COUNT],
+    // just pressed, or held long enough to repeat
+    repeated: [bool; Button::BUTTON_COUNT],
+    text: Vec<TextInput>,
+}
+
+impl UiInput {
+    fn from_input(input: &Input) -> Self {
+        let mut just_pressed = [false; Button::BUTTON_COUNT];
+        let mut repeated = [false; Button::BUTTON_COUNT];
+        for button in Button::iter() {
+            just_pressed[button.index()] = input.just_pressed(button);
+            repeated[button.index()] = input.just_pressed_repeat(button);
+        }
+
+        Self {
+            just_pressed,
+            repeated,
+            text: input.text_input().to_vec(),
+        }
+    }
+
+    fn just_pressed(&self, button: Button) -> bool {
+        self.just_pressed[button.index()]
+    }
+
+    fn repeated(&self, button: Button) -> bool {
+        self.repeated[button.index()]
+    }
+
+    fn release(&mut self, button: Button) {
+        self.just_pressed[button.index()] = false;
+        self.repeated[button.index()] = false;
+    }
+}
+
+impl Default for UiInput {
+    fn default() -> Self {
+        Self {
+            just_pressed: [false; Button::BUTTON_COUNT],
+            repeated: [false; Button::BUTTON_COUNT],
+            text: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScrollAreaState {
+    id: Id,
+    // top left of the visible part
+    origin: IVec2,
+    size: UVec2,
+    offset: i32,
+    // top and bottom of the focused widget, relative to the content
+    focused: Option<(i32, i32)>,
+    previous_cursor: IVec2,
+    previous_layout_width: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ColumnsState {
+    count: u32,
+    column_width: u32,
+    column_index: u32,
+    origin: IVec2,
+    max_y: i32,
+    previous_layout_width: u32,
+}
+
+fn normalized_range<T: PartialOrd + Copy>(range: RangeInclusive<T>) -> (T, T) {
+    let start = *range.start();
+    let end = *range.end();
+    if start <= end {
+        (start, end)
+    } else {
+        (end, start)
+    }
+}
+
+fn apply_step_input<T>(input: &UiInput, value: &mut T, range: RangeInclusive<T>, speed: T) -> bool
+where
+    T: Num + Copy + PartialOrd + ToPrimitive + NumCast,
+{
+    let (min, max) = normalized_range(range);
+    let Some(min_f) = min.to_f32() else {
+        return false;
+    };
+    let Some(max_f) = max.to_f32() else {
+        return false;
+    };
+    let Some(value_f) = value.to_f32() else {
+        return false;
+    };
+    let Some(speed_f) = speed.to_f32() else {
+        return false;
+    };
+
+    let step = speed_f.abs();
+    if step == 0.0 {
+        return false;
+    }
+
+    let mut next_value = value_f;
+    if input.repeated(Button::Left) {
+        next_value -= step;
+    }
+    if input.repeated(Button::Right) {
+        next_value += step;
+    }
+    next_value = next_value.clamp(min_f, max_f);
+
+    if (next_value - value_f).abs() > f32::EPSILON
+        && let Some(next_value) = NumCast::from(next_value)
+    {
+        *value = next_value;
+        return true;
+    }
+
+    false
+}
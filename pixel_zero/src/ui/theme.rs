@@ -0,0 +1,238 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use thiserror::Error;
+
+use crate::graphics::{Color, Font};
+
+#[derive(Debug, Error)]
+pub enum ThemeError {
+    #[error("I/O error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Line {0}: expected `key = value`")]
+    InvalidLine(usize),
+    #[error("Line {0}: unknown key `{1}`")]
+    UnknownKey(usize, String),
+    #[error("Line {0}: invalid value `{1}`")]
+    InvalidValue(usize, String),
+}
+
+/// Colors, sizes and font of [`Ui`](crate::ui::Ui) widgets.
+///
+/// Theme files contain `key = value` lines, with the field names as keys.
+/// Colors are `#rrggbb` or `#rrggbbaa`, keys that are left out keep their default:
+///
+/// ```text
+/// # a red theme
+/// widget_bg = #301010
+/// focus_outline = #ff8080
+/// padding = 4
+/// layout_width = 200
+/// ```
+///
+/// Fonts need the graphics context to load, so they are only set in code.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Font of all text, `None` uses the font the `Ui` was created with.
+    pub font: Option<Font>,
+    /// Space between the screen edge and widgets.
+    pub padding: i32,
+    /// Space between widgets.
+    pub spacing: i32,
+    /// Width of widgets, `None` fills the screen.
+    pub layout_width: Option<u32>,
+    pub checkbox_size: u32,
+    pub progress_height: u32,
+    pub separator_thickness: u32,
+    /// Space between the text and the border of widgets.
+    pub widget_padding: i32,
+    pub scrollbar_width: u32,
+    pub widget_bg: Color,
+    pub widget_bg_focused: Color,
+    pub widget_border: Color,
+    pub checkbox_fill: Color,
+    pub progress_track: Color,
+    pub progress_fill: Color,
+    pub separator: Color,
+    pub focus_outline: Color,
+    pub scrollbar_track: Color,
+    pub scrollbar_thumb: Color,
+    /// Drawn over everything below a dialog, usually translucent.
+    pub modal_dim: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            font: None,
+            padding: 2,
+            spacing: 2,
+            layout_width: None,
+            checkbox_size: 12,
+            progress_height: 6,
+            separator_thickness: 1,
+            widget_padding: 2,
+            scrollbar_width: 3,
+            widget_bg: Color::rgb(24, 24, 32),
+            widget_bg_focused: Color::rgb(50, 50, 70),
+            widget_border: Color::rgb(90, 90, 90),
+            checkbox_fill: Color::rgb(220, 220, 220),
+            progress_track: Color::rgb(60, 60, 60),
+            progress_fill: Color::rgb(120, 160, 220),
+            separator: Color::rgb(80, 80, 80),
+            focus_outline: Color::YELLOW,
+            scrollbar_track: Color::rgb(40, 40, 40),
+            scrollbar_thumb: Color::rgb(140, 140, 140),
+            modal_dim: Color::rgba(0, 0, 0, 160),
+        }
+    }
+}
+
+impl Theme {
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or is malformed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThemeError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Writes every key except the font.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ThemeError> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    fn sizes(&mut self) -> [(&'static str, &mut i32); 3] {
+        [
+            ("padding", &mut self.padding),
+            ("spacing", &mut self.spacing),
+            ("widget_padding", &mut self.widget_padding),
+        ]
+    }
+
+    fn unsigned_sizes(&mut self) -> [(&'static str, &mut u32); 4] {
+        [
+            ("checkbox_size", &mut self.checkbox_size),
+            ("progress_height", &mut self.progress_height),
+            ("separator_thickness", &mut self.separator_thickness),
+            ("scrollbar_width", &mut self.scrollbar_width),
+        ]
+    }
+
+    fn colors(&mut self) -> [(&'static str, &mut Color); 11] {
+        [
+            ("widget_bg", &mut self.widget_bg),
+            ("widget_bg_focused", &mut self.widget_bg_focused),
+            ("widget_border", &mut self.widget_border),
+            ("checkbox_fill", &mut self.checkbox_fill),
+            ("progress_track", &mut self.progress_track),
+            ("progress_fill", &mut self.progress_fill),
+            ("separator", &mut self.separator),
+            ("focus_outline", &mut self.focus_outline),
+            ("scrollbar_track", &mut self.scrollbar_track),
+            ("scrollbar_thumb", &mut self.scrollbar_thumb),
+            ("modal_dim", &mut self.modal_dim),
+        ]
+    }
+
+    /// Sets the field named `key`, `None` if the key is unknown.
+    fn set(&mut self, key: &str, value: &str) -> Option<Result<(), ()>> {
+        if key == "layout_width" {
+            self.layout_width = match value {
+                "none" => None,
+                value => Some(value.parse().map_err(|_| ()).ok()?),
+            };
+            return Some(Ok(()));
+        }
+        if let Some((_, field)) = self.sizes().into_iter().find(|(name, _)| *name == key) {
+            return Some(value.parse().map(|value| *field = value).map_err(|_| ()));
+        }
+        if let Some((_, field)) = self
+            .unsigned_sizes()
+            .into_iter()
+            .find(|(name, _)| *name == key)
+        {
+            return Some(value.parse().map(|value| *field = value).map_err(|_| ()));
+        }
+        if let Some((_, field)) = self.colors().into_iter().find(|(name, _)| *name == key) {
+            return Some(parse_color(value).map(|color| *field = color));
+        }
+        None
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the field accessors need `&mut`, so they are read from a copy
+        let mut theme = self.clone();
+        match theme.layout_width {
+            Some(width) => writeln!(f, "layout_width = {width}")?,
+            None => writeln!(f, "layout_width = none")?,
+        }
+        for (name, value) in theme.sizes() {
+            writeln!(f, "{name} = {value}")?;
+        }
+        for (name, value) in theme.unsigned_sizes() {
+            writeln!(f, "{name} = {value}")?;
+        }
+        for (name, color) in theme.colors() {
+            writeln!(
+                f,
+                "{name} = #{:02x}{:02x}{:02x}{:02x}",
+                color.r(),
+                color.g(),
+                color.b(),
+                color.a()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut theme = Self::default();
+        for (index, line) in s.lines().enumerate() {
+            let number = index + 1;
+            // `#` also starts colors, so only whole line comments are allowed
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(ThemeError::InvalidLine(number))?;
+            let (key, value) = (key.trim(), value.trim());
+            theme
+                .set(key, value)
+                .ok_or_else(|| ThemeError::UnknownKey(number, key.to_owned()))?
+                .map_err(|()| ThemeError::InvalidValue(number, value.to_owned()))?;
+        }
+        Ok(theme)
+    }
+}
+
+/// Parses `#rrggbb` or `#rrggbbaa`.
+fn parse_color(value: &str) -> Result<Color, ()> {
+    let hex = value.strip_prefix('#').ok_or(())?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return Err(());
+    }
+    let channel = |index: usize| {
+        hex.get(index * 2..index * 2 + 2)
+            .map_or(Ok(255), |channel| u8::from_str_radix(channel, 16))
+            .map_err(|_| ())
+    };
+    Ok(Color::rgba(
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        channel(3)?,
+    ))
+}
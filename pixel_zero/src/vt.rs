@@ -0,0 +1,151 @@
+use std::{
+    fs::File,
+    os::fd::AsRawFd as _,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use nix::{
+    ioctl_read_bad, ioctl_write_int_bad, ioctl_write_ptr_bad,
+    libc::c_int,
+    sys::signal::{SaFlags, SigAction, SigHandler, SigSet, Signal, sigaction},
+};
+use parking_lot::Mutex;
+
+const VT_GETMODE: u16 = 0x5601;
+const VT_SETMODE: u16 = 0x5602;
+const VT_RELDISP: u16 = 0x5605;
+
+const VT_PROCESS: i8 = 1;
+const VT_ACKACQ: c_int = 2;
+
+const RELEASE_SIGNAL: Signal = Signal::SIGUSR1;
+const ACQUIRE_SIGNAL: Signal = Signal::SIGUSR2;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct VtMode {
+    mode: i8,
+    waitv: i8,
+    relsig: i16,
+    acqsig: i16,
+    frsig: i16,
+}
+
+ioctl_read_bad!(vt_getmode, VT_GETMODE, VtMode);
+ioctl_write_ptr_bad!(vt_setmode, VT_SETMODE, VtMode);
+ioctl_write_int_bad!(vt_reldisp, VT_RELDISP);
+
+static RELEASE_REQUESTED: AtomicBool = AtomicBool::new(false);
+static ACQUIRE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// The terminal and its original mode, to restore when panicking, since `Drop` might never run.
+static PANIC_RESTORE: Mutex<Option<(File, VtMode)>> = Mutex::new(None);
+
+/// Gives switching back to the kernel. Called from the display's panic hook.
+pub(crate) fn restore_after_panic() {
+    // the lock might be held by the panicking thread
+    if let Some(guard) = PANIC_RESTORE.try_lock()
+        && let Some((tty, original)) = guard.as_ref()
+    {
+        let _ = unsafe { vt_setmode(tty.as_raw_fd(), original) };
+    }
+}
+
+extern "C" fn handle_signal(signal: c_int) {
+    if signal == RELEASE_SIGNAL as c_int {
+        RELEASE_REQUESTED.store(true, Ordering::Relaxed);
+    } else if signal == ACQUIRE_SIGNAL as c_int {
+        ACQUIRE_REQUESTED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A requested virtual terminal switch, which has to be acknowledged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VtSwitch {
+    /// Switching away, the display has to be released.
+    Release,
+    /// Switching back, the display can be used again.
+    Acquire,
+}
+
+/// Takes over virtual terminal switching, so the kernel asks before switching away from us.
+pub(crate) struct VtGuard {
+    tty: File,
+    original: VtMode,
+}
+
+impl VtGuard {
+    /// # Errors
+    ///
+    /// Fails when not running on a virtual terminal.
+    pub fn new() -> std::io::Result<Self> {
+        let tty = File::options().read(true).write(true).open("/dev/tty")?;
+
+        let mut original = VtMode::default();
+        unsafe { vt_getmode(tty.as_raw_fd(), &raw mut original) }?;
+
+        let action = SigAction::new(
+            SigHandler::Handler(handle_signal),
+            SaFlags::SA_RESTART,
+            SigSet::empty(),
+        );
+        unsafe {
+            sigaction(RELEASE_SIGNAL, &action)?;
+            sigaction(ACQUIRE_SIGNAL, &action)?;
+        }
+
+        Self::take_switching(&tty)?;
+        *PANIC_RESTORE.lock() = Some((tty.try_clone()?, original));
+
+        Ok(Self { tty, original })
+    }
+
+    /// Takes over switching again, after a caught panic gave it back.
+    pub fn retake(&self) -> std::io::Result<()> {
+        Self::take_switching(&self.tty)
+    }
+
+    fn take_switching(tty: &File) -> std::io::Result<()> {
+        let mode = VtMode {
+            mode: VT_PROCESS,
+            waitv: 0,
+            relsig: RELEASE_SIGNAL as i16,
+            acqsig: ACQUIRE_SIGNAL as i16,
+            frsig: 0,
+        };
+        unsafe { vt_setmode(tty.as_raw_fd(), &raw const mode) }?;
+        Ok(())
+    }
+
+    /// Returns a pending switch request. Releases take precedence.
+    pub fn poll(&self) -> Option<VtSwitch> {
+        if RELEASE_REQUESTED.swap(false, Ordering::Relaxed) {
+            Some(VtSwitch::Release)
+        } else if ACQUIRE_REQUESTED.swap(false, Ordering::Relaxed) {
+            Some(VtSwitch::Acquire)
+        } else {
+            None
+        }
+    }
+
+    /// Allows the kernel to switch away, after the display has been released.
+    pub fn acknowledge_release(&self) -> std::io::Result<()> {
+        unsafe { vt_reldisp(self.tty.as_raw_fd(), 1) }?;
+        Ok(())
+    }
+
+    /// Confirms that we are back on our terminal.
+    pub fn acknowledge_acquire(&self) -> std::io::Result<()> {
+        unsafe { vt_reldisp(self.tty.as_raw_fd(), VT_ACKACQ) }?;
+        Ok(())
+    }
+}
+
+impl Drop for VtGuard {
+    fn drop(&mut self) {
+        PANIC_RESTORE.lock().take();
+        if let Err(e) = unsafe { vt_setmode(self.tty.as_raw_fd(), &raw const self.original) } {
+            log::error!("failed to reset vt mode: {e}");
+        }
+    }
+}